@@ -1,3 +1,6 @@
 #![cfg_attr(not(test), no_std)]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 mod raw_list;
 pub use raw_list::*;
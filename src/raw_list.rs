@@ -1,5 +1,10 @@
-use core::{marker::PhantomData, ptr::NonNull};
+use core::{cmp::Ordering, marker::PhantomData, ptr::NonNull};
 
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, collections::BTreeSet, vec::Vec};
+
+// repr(C) so Node<T> and Node<U> line up whenever T and U do, see map_in_place_same_size
+#[repr(C)]
 pub struct Node<T>
 {
   next: Link<T>,
@@ -14,15 +19,47 @@ pub struct List<T>
   front: Link<T>,
   back: Link<T>,
   len: usize,
+  // Bumped on every structural change, debug builds only, see CursorMut::check_generation
+  #[cfg(debug_assertions)]
+  generation: u64,
 
   _phantom: PhantomData<T>,
 }
 
+// Besides sitting on a node, a cursor can be on the "ghost": a non-element between the back and the
+// front, which is where a new cursor starts. move_next off the ghost lands on the front and move_prev
+// on the back, inserting before the ghost appends to the back and inserting after it prepends.
+// To insert before the first element, move_to_front and then insert_before.
 pub struct CursorMut<'a, T>
 {
   idx: Option<usize>,
   current: Link<T>,
   list: &'a mut List<T>,
+  // The list's generation as of the last time this cursor touched it
+  #[cfg(debug_assertions)]
+  generation: u64,
+}
+
+pub struct Iter<'a, T>
+{
+  current: Link<T>,
+  remaining: usize,
+  _phantom: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T>
+{
+  current: Link<T>,
+  remaining: usize,
+  _phantom: PhantomData<&'a mut T>,
+}
+
+// Which way List::rotate moves the elements. Left brings later elements towards the front.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RotateDir
+{
+  Left,
+  Right,
 }
 
 impl<T> List<T>
@@ -33,6 +70,25 @@ impl<T> List<T>
       front: None,
       back: None,
       len: 0,
+      #[cfg(debug_assertions)]
+      generation: 0,
+      _phantom: PhantomData,
+    }
+  }
+
+  // A list holding just node, whatever links node had are reset.
+  pub fn with_single(node: NonNull<Node<T>>) -> Self
+  {
+    unsafe {
+      (*node.as_ptr()).next = None;
+      (*node.as_ptr()).prev = None;
+    }
+    Self {
+      front: Some(node),
+      back: Some(node),
+      len: 1,
+      #[cfg(debug_assertions)]
+      generation: 0,
       _phantom: PhantomData,
     }
   }
@@ -41,6 +97,9 @@ impl<T> List<T>
   pub fn push_front(&mut self, node: NonNull<Node<T>>)
   {
     unsafe {
+      // Whatever links node had from a previous list are stale, it has nothing on either side yet
+      (*node.as_ptr()).prev = None;
+      (*node.as_ptr()).next = None;
       if let Some(old_front) = self.front
       {
         (*node.as_ptr()).next = Some(old_front); // Make sure the new node's back is now the old front
@@ -52,12 +111,15 @@ impl<T> List<T>
       }
       self.front = Some(node); // front is now the new node
       self.len += 1; // increase len
+      self.bump_generation();
     }
   }
 
   pub fn push_back(&mut self, node: NonNull<Node<T>>)
   {
     unsafe {
+      (*node.as_ptr()).prev = None;
+      (*node.as_ptr()).next = None;
       if let Some(old_back) = self.back
       {
         (*node.as_ptr()).prev = Some(old_back);
@@ -69,14 +131,16 @@ impl<T> List<T>
       }
       self.back = Some(node);
       self.len += 1;
+      self.bump_generation();
     }
   }
 
   pub fn pop_front(&mut self) -> Link<T>
   {
     unsafe {
-      self.front.map(|old_front| {
+      self.front.inspect(|&old_front| {
         self.front = (*old_front.as_ptr()).next; // set the front to the next node
+        (*old_front.as_ptr()).next = None; // the popped node shouldn't keep pointing into the list
         if let Some(new_front) = self.front
         {
           // make sure the new front isnt pointing to the old front
@@ -87,7 +151,7 @@ impl<T> List<T>
           self.back = None
         }
         self.len -= 1;
-        old_front
+        self.bump_generation();
       })
     }
   }
@@ -95,8 +159,9 @@ impl<T> List<T>
   pub fn pop_back(&mut self) -> Link<T>
   {
     unsafe {
-      self.back.map(|old_back| {
+      self.back.inspect(|&old_back| {
         self.back = (*old_back.as_ptr()).prev;
+        (*old_back.as_ptr()).prev = None;
         if let Some(new_back) = self.back
         {
           (*new_back.as_ptr()).next = None;
@@ -106,7 +171,7 @@ impl<T> List<T>
           self.front = None;
         }
         self.len -= 1;
-        old_back
+        self.bump_generation();
       })
     }
   }
@@ -129,11 +194,37 @@ impl<T> List<T>
     unsafe { self.back.map(|x| &mut (*x.as_ptr()).elem) }
   }
 
+  pub fn front_node(&self) -> Link<T>
+  {
+    self.front
+  }
+  pub fn back_node(&self) -> Link<T>
+  {
+    self.back
+  }
+
+  // The front element together with the node holding it, saving a second lookup before a pop.
+  pub fn front_with_node(&self) -> Option<(&T, NonNull<Node<T>>)>
+  {
+    unsafe { self.front.map(|x| (&(*x.as_ptr()).elem, x)) }
+  }
+  pub fn back_with_node(&self) -> Option<(&T, NonNull<Node<T>>)>
+  {
+    unsafe { self.back.map(|x| (&(*x.as_ptr()).elem, x)) }
+  }
+
   pub fn len(&self) -> usize
   {
     self.len
   }
 
+  // Index of the back element, None when empty. Use this rather than len() - 1 when doing index math
+  // against a cursor so an empty list can't wrap around to usize::MAX.
+  pub fn len_minus_one(&self) -> Option<usize>
+  {
+    self.len.checked_sub(1)
+  }
+
   pub fn cursor_mut<'a>(&'a mut self) -> CursorMut<'a, T>
   {
     CursorMut::new(self)
@@ -143,339 +234,4969 @@ impl<T> List<T>
   {
     self.len == 0
   }
-}
 
-impl<T> Node<T>
-{
-  pub fn new(data: T) -> Self
+  pub fn is_empty(&self) -> bool
+  {
+    self.empty()
+  }
+
+  /// Builds a list straight from its raw parts, no links are touched.
+  ///
+  /// # Safety
+  /// `front` must start a chain of valid nodes that ends at `back`, with every `prev` link mirroring
+  /// the `next` links, and `len` must be the number of nodes in that chain.
+  pub unsafe fn from_parts(front: Link<T>, back: Link<T>, len: usize) -> Self
   {
     Self {
-      next: None,
-      prev: None,
-      elem: data,
+      front,
+      back,
+      len,
+      #[cfg(debug_assertions)]
+      generation: 0,
+      _phantom: PhantomData,
     }
   }
-  pub fn elem(&self) -> &T
+
+  /// Wraps a chain the caller linked up themselves, running from `front` to `back`.
+  ///
+  /// # Safety
+  /// Same as from_parts: the chain has to be well formed and exactly `len` nodes long.
+  pub unsafe fn from_raw_nodes(front: NonNull<Node<T>>, back: NonNull<Node<T>>, len: usize)
+  -> Self
   {
-    &self.elem
+    unsafe { Self::from_parts(Some(front), Some(back), len) }
   }
-  pub fn elem_mut(&mut self) -> &mut T
+
+  /// from_raw_nodes that walks the chain first, returning None unless following `next` from `front`
+  /// reaches `back` in exactly `len` nodes with every `prev` link mirroring it and nothing hanging
+  /// off either end. O(len).
+  ///
+  /// # Safety
+  /// Every node reachable from `front` within `len` steps must be valid.
+  pub unsafe fn try_from_raw_nodes(
+    front: NonNull<Node<T>>,
+    back: NonNull<Node<T>>,
+    len: usize,
+  ) -> Option<Self>
   {
-    &mut self.elem
+    unsafe {
+      if len == 0 || (*front.as_ptr()).prev.is_some()
+      {
+        return None;
+      }
+      let mut node = front;
+      for _ in 1..len
+      {
+        let next = (*node.as_ptr()).next?;
+        if (*next.as_ptr()).prev != Some(node)
+        {
+          return None;
+        }
+        node = next;
+      }
+      if node != back || (*back.as_ptr()).next.is_some()
+      {
+        return None;
+      }
+      Some(Self::from_raw_nodes(front, back, len))
+    }
   }
 
-  pub fn next_node(&self) -> Link<T>
+  /// Panics if following the `next` links from the front ever loops back on itself.
+  /// Only checks in debug builds.
+  pub fn assert_acyclic(&self)
   {
-    self.next
+    if !cfg!(debug_assertions)
+    {
+      return;
+    }
+    unsafe {
+      // Floyd's tortoise and hare, the hare moves two nodes for every one the tortoise moves
+      let mut slow = self.front;
+      let mut fast = self.front;
+      let mut steps = 0;
+      while let Some(f) = fast
+      {
+        fast = (*f.as_ptr()).next;
+        match fast
+        {
+          Some(f) => fast = (*f.as_ptr()).next,
+          None => break,
+        }
+        slow = slow.and_then(|x| (*x.as_ptr()).next);
+        assert!(slow != fast, "cycle detected in list");
+
+        // Backstop in case len is wrong as well, an acyclic chain of len nodes never gets this far
+        steps += 1;
+        assert!(
+          steps <= 2 * self.len,
+          "walked past 2 * len nodes, list is likely cyclic"
+        );
+      }
+    }
   }
-  pub fn prev_node(&self) -> Link<T>
+
+  /// Panics if the links, `back` and `len` don't agree with each other.
+  /// Only checks in debug builds.
+  pub fn check_invariants(&self)
   {
-    self.prev
+    if !cfg!(debug_assertions)
+    {
+      return;
+    }
+    // Has to come first, the walk below would never end on a cyclic list
+    self.assert_acyclic();
+    unsafe {
+      let mut count = 0;
+      let mut prev: Link<T> = None;
+      let mut current = self.front;
+      while let Some(node) = current
+      {
+        assert!(
+          (*node.as_ptr()).prev == prev,
+          "broken prev link at index {}",
+          count
+        );
+        prev = current;
+        current = (*node.as_ptr()).next;
+        count += 1;
+      }
+      assert!(prev == self.back, "back does not point at the last node");
+      assert!(
+        count == self.len,
+        "len is {} but the list holds {} nodes",
+        self.len,
+        count
+      );
+    }
   }
-}
 
-impl<'a, T> CursorMut<'a, T>
-{
-  pub(self) fn new(list: &'a mut List<T>) -> Self
+  // Cuts the chain first..=last out of the list and hands it back as its own list.
+  // The caller has to guarantee first comes before (or is) last and that count is the length of that run.
+  pub(self) unsafe fn unlink_range(
+    &mut self,
+    first: NonNull<Node<T>>,
+    last: NonNull<Node<T>>,
+    count: usize,
+  ) -> List<T>
   {
-    Self {
-      idx: None,
-      current: None,
-      list,
+    unsafe {
+      let before = (*first.as_ptr()).prev;
+      let after = (*last.as_ptr()).next;
+
+      // Stitch the nodes on either side of the range together
+      match before
+      {
+        Some(b) => (*b.as_ptr()).next = after,
+        None => self.front = after,
+      }
+      match after
+      {
+        Some(a) => (*a.as_ptr()).prev = before,
+        None => self.back = before,
+      }
+      self.len -= count;
+      self.bump_generation();
+
+      (*first.as_ptr()).prev = None;
+      (*last.as_ptr()).next = None;
+      List::from_parts(Some(first), Some(last), count)
     }
   }
 
-  pub fn move_next(&mut self)
+  // Compares two lists element by element with a custom equality, no PartialEq needed.
+  pub fn eq_by<F: FnMut(&T, &T) -> bool>(&self, other: &List<T>, mut eq: F) -> bool
   {
-    if let Some(node) = self.current
+    if self.len != other.len
     {
-      self.current = unsafe { (*node.as_ptr()).next };
-      if let Some(_) = self.current
+      return false;
+    }
+    let (mut a, mut b) = (self.front, other.front);
+    unsafe {
+      while let (Some(x), Some(y)) = (a, b)
       {
-        if Some(self.list.len) == self.idx
+        if !eq(&(*x.as_ptr()).elem, &(*y.as_ptr()).elem)
         {
-          self.idx = None
-        }
-        else
-        {
-          self.idx = Some(self.idx.map_or(0, |x| x + 1));
+          return false;
         }
+        a = (*x.as_ptr()).next;
+        b = (*y.as_ptr()).next;
       }
-      else
+    }
+    true
+  }
+
+  // Reverses the values held by the count nodes starting at first, the nodes themselves stay put.
+  pub(self) unsafe fn reverse_values(first: NonNull<Node<T>>, count: usize)
+  {
+    if count < 2
+    {
+      return;
+    }
+    unsafe {
+      let mut last = first;
+      for _ in 1..count
       {
-        self.idx = None;
+        last = (*last.as_ptr()).next.unwrap();
+      }
+      // Swap inwards from both ends until they meet
+      let mut lo = first;
+      let mut hi = last;
+      for _ in 0..count / 2
+      {
+        core::ptr::swap(&mut (*lo.as_ptr()).elem, &mut (*hi.as_ptr()).elem);
+        lo = (*lo.as_ptr()).next.unwrap();
+        hi = (*hi.as_ptr()).prev.unwrap();
       }
     }
+  }
+
+  // Moves every value n positions towards the front (wrapping around), the node chain is not touched.
+  // Handy when outside code holds node pointers and expects them to stay where they are.
+  pub fn shift_values_left(&mut self, n: usize)
+  {
+    let Some(front) = self.front
     else
     {
-      self.idx = Some(0);
-      self.current = self.list.front;
+      return;
+    };
+    let n = n % self.len;
+    if n == 0
+    {
+      return;
+    }
+    unsafe {
+      // Rotating by reversals: reverse [0, n), reverse [n, len), then reverse the whole thing
+      let mut split = front;
+      for _ in 0..n
+      {
+        split = (*split.as_ptr()).next.unwrap();
+      }
+      Self::reverse_values(front, n);
+      Self::reverse_values(split, self.len - n);
+      Self::reverse_values(front, self.len);
     }
   }
-  pub fn move_prev(&mut self)
+
+  // Same as shift_values_left but towards the back.
+  pub fn shift_values_right(&mut self, n: usize)
   {
-    if let Some(node) = self.current
+    if self.len == 0
     {
-      self.current = unsafe { (*node.as_ptr()).prev };
-      if let Some(_) = self.current
+      return;
+    }
+    self.shift_values_left(self.len - n % self.len);
+  }
+
+  pub(self) fn bump_generation(&mut self)
+  {
+    #[cfg(debug_assertions)]
+    {
+      self.generation = self.generation.wrapping_add(1);
+    }
+  }
+
+  // Splits the list in two at node, everything from node to the back is returned as a new list.
+  // node has to be in this list, passing any other node is undefined behavior.
+  pub fn split_at_node(&mut self, node: NonNull<Node<T>>) -> List<T>
+  {
+    unsafe {
+      // Walk outwards from node in both directions at once, whichever end we hit first tells us
+      // the length of that side without having to walk the longer one
+      let mut steps = 0;
+      let mut back_walk = (*node.as_ptr()).prev;
+      let mut front_walk = (*node.as_ptr()).next;
+      let tail_len = loop
       {
-        if Some(0) == self.idx
+        match (back_walk, front_walk)
         {
-          self.idx = None;
+          (None, _) => break self.len - steps, // steps nodes sit before node
+          (_, None) => break steps + 1,        // steps nodes sit after node
+          (Some(b), Some(f)) =>
+          {
+            steps += 1;
+            back_walk = (*b.as_ptr()).prev;
+            front_walk = (*f.as_ptr()).next;
+          }
         }
-        else
+      };
+      self.unlink_range(node, self.back.unwrap(), tail_len)
+    }
+  }
+
+  // Walks from the front and returns the first node whose element matches pred.
+  pub fn first_node_where<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Link<T>
+  {
+    let mut current = self.front;
+    unsafe {
+      while let Some(node) = current
+      {
+        if pred(&(*node.as_ptr()).elem)
+        {
+          return Some(node);
+        }
+        current = (*node.as_ptr()).next;
+      }
+    }
+    None
+  }
+
+  // Walks from the back and returns the last node whose element matches pred.
+  pub fn last_node_where<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Link<T>
+  {
+    let mut current = self.back;
+    unsafe {
+      while let Some(node) = current
+      {
+        if pred(&(*node.as_ptr()).elem)
         {
-          self.idx = Some(self.idx.map_or(self.list.len() - 1, |x| x - 1));
+          return Some(node);
         }
+        current = (*node.as_ptr()).prev;
+      }
+    }
+    None
+  }
+
+  // Gathers references to every element front to back, for when random access is needed.
+  #[cfg(feature = "alloc")]
+  pub fn collect_refs(&self) -> Vec<&T>
+  {
+    let mut ret = Vec::with_capacity(self.len);
+    let mut current = self.front;
+    while let Some(node) = current
+    {
+      unsafe {
+        ret.push(&(*node.as_ptr()).elem);
+        current = (*node.as_ptr()).next;
       }
+    }
+    ret
+  }
+
+  // Merges k individually sorted lists into one sorted list by relinking their nodes.
+  // The lists sit in a binary min-heap keyed on their fronts, so each node costs O(log k). Ties go to
+  // the earlier list, which keeps the merge stable.
+  #[cfg(feature = "alloc")]
+  pub fn merge_k_sorted<F: FnMut(&T, &T) -> Ordering>(
+    mut lists: Vec<List<T>>,
+    mut cmp: F,
+  ) -> List<T>
+  {
+    // Whether list a's front has to come out before list b's
+    let mut before = |lists: &[List<T>], a: usize, b: usize| unsafe {
+      let (Some(x), Some(y)) = (lists[a].front, lists[b].front)
       else
       {
-        self.idx = None;
+        return false;
+      };
+      match cmp(&(*x.as_ptr()).elem, &(*y.as_ptr()).elem)
+      {
+        Ordering::Less => true,
+        Ordering::Greater => false,
+        Ordering::Equal => a < b,
+      }
+    };
+    let mut sift_down = |heap: &mut [usize], lists: &[List<T>], mut i: usize| {
+      loop
+      {
+        let mut first = i;
+        for child in [2 * i + 1, 2 * i + 2]
+        {
+          if child < heap.len() && before(lists, heap[child], heap[first])
+          {
+            first = child;
+          }
+        }
+        if first == i
+        {
+          break;
+        }
+        heap.swap(i, first);
+        i = first;
       }
+    };
+
+    let mut heap: Vec<usize> = (0..lists.len()).filter(|&i| !lists[i].is_empty()).collect();
+    for i in (0..heap.len() / 2).rev()
+    {
+      sift_down(&mut heap, &lists, i);
     }
-    else
+
+    let mut ret = List::new();
+    while let Some(&top) = heap.first()
     {
-      self.idx = Some(self.list.len() - 1);
-      self.current = self.list.back;
+      if let Some(node) = lists[top].pop_front()
+      {
+        ret.push_back(node);
+      }
+      if lists[top].is_empty()
+      {
+        heap.swap_remove(0);
+      }
+      sift_down(&mut heap, &lists, 0);
     }
+    ret
   }
 
-  pub fn current_value(&mut self) -> Option<&mut T>
+  // Unlinks every node in nodes from the list, resetting their links.
+  // Every node has to actually be in this list (and only appear once), anything else is undefined behavior.
+  pub fn bulk_remove_nodes(&mut self, nodes: &[NonNull<Node<T>>])
   {
-    self.current.map(|x| unsafe { &mut (*x.as_ptr()).elem })
+    for &node in nodes
+    {
+      unsafe { self.unlink_range(node, node, 1) };
+    }
   }
 
-  pub fn current_link(&mut self) -> Link<T>
+  // Pushes to the front unless the list already holds max nodes, in which case the node is handed back.
+  pub fn try_push_front(
+    &mut self,
+    node: NonNull<Node<T>>,
+    max: usize,
+  ) -> Result<(), NonNull<Node<T>>>
   {
-    self.current
+    if self.len >= max
+    {
+      return Err(node);
+    }
+    self.push_front(node);
+    Ok(())
   }
 
-  pub fn remove(&mut self) -> Link<T>
+  // Pushes to the back unless the list already holds max nodes, in which case the node is handed back.
+  pub fn try_push_back(
+    &mut self,
+    node: NonNull<Node<T>>,
+    max: usize,
+  ) -> Result<(), NonNull<Node<T>>>
   {
-    let link_current = self.current;
-    self.move_next();
+    if self.len >= max
+    {
+      return Err(node);
+    }
+    self.push_back(node);
+    Ok(())
+  }
 
+  // Swaps the elements held by a and b, the links stay as they are.
+  // Both nodes have to be in this list.
+  pub fn elem_swap(&mut self, a: NonNull<Node<T>>, b: NonNull<Node<T>>)
+  {
+    if a == b
+    {
+      return;
+    }
+    unsafe { core::mem::swap(&mut (*a.as_ptr()).elem, &mut (*b.as_ptr()).elem) };
+  }
+
+  // The node at idx, walking in from whichever end is closer.
+  pub(self) fn node_at(&self, idx: usize) -> Link<T>
+  {
+    if idx >= self.len
+    {
+      return None;
+    }
     unsafe {
-      if let Some(p_current) = link_current
+      if idx < self.len / 2
       {
-        self.idx = self.idx.map(|x| x - 1);
-
-        let current = &mut (*p_current.as_ptr());
-        let (o_prev, o_next) = (current.prev, current.next);
-
-        match (o_prev, o_next)
+        let mut node = self.front;
+        for _ in 0..idx
         {
-          (None, None) => self.list.pop_front(), // Only node in the list, therefore pop front will do the trick
-          (None, Some(_)) => self.list.pop_front(), // its the front node
-          (Some(_), None) => self.list.pop_back(), // its the back node
-          (Some(p), Some(n)) =>
-          {
-            // Its the middle node, stitch the two nodes together
-            (*p.as_ptr()).next = Some(n);
-            (*n.as_ptr()).prev = Some(p);
-            self.list.len -= 1;
-            current.prev = None;
-            current.next = None;
-            link_current
-          }
+          node = (*node?.as_ptr()).next;
         }
+        node
       }
       else
       {
-        link_current
+        let mut node = self.back;
+        for _ in idx + 1..self.len
+        {
+          node = (*node?.as_ptr()).prev;
+        }
+        node
       }
     }
   }
 
-  pub fn insert_before(&mut self, node: NonNull<Node<T>>)
+  // Reverses the run of nodes first..=last in place by relinking, reconnecting whatever surrounds it.
+  // first has to come before (or be) last in this list.
+  pub(self) unsafe fn reverse_chain(&mut self, first: NonNull<Node<T>>, last: NonNull<Node<T>>)
   {
-    let link_current = self.current;
     unsafe {
-      if let Some(p_current) = link_current
-      {
-        let current = &mut (*p_current.as_ptr());
-        if let Some(p_prev) = current.prev
-        {
-          let prev = &mut (*p_prev.as_ptr());
-          let new = &mut (*node.as_ptr());
-          prev.next = Some(node);
-          current.prev = Some(node);
+      let before = (*first.as_ptr()).prev;
+      let after = (*last.as_ptr()).next;
 
-          new.prev = Some(p_prev);
-          new.next = Some(p_current);
-          self.idx = self.idx.map(|x| x + 1);
-          self.list.len += 1;
-        }
-        // at front
-        else
+      // Flip every link inside the run
+      let mut node = Some(first);
+      while let Some(x) = node
+      {
+        let n = &mut (*x.as_ptr());
+        node = n.next;
+        core::mem::swap(&mut n.next, &mut n.prev);
+        if x == last
         {
-          self.list.push_front(node);
-          self.idx = self.idx.map(|x| x + 1);
+          break;
         }
       }
+
+      // and hook the flipped run back in, last now leads and first trails
+      (*last.as_ptr()).prev = before;
+      (*first.as_ptr()).next = after;
+      match before
+      {
+        Some(b) => (*b.as_ptr()).next = Some(last),
+        None => self.front = Some(last),
+      }
+      match after
+      {
+        Some(a) => (*a.as_ptr()).prev = Some(first),
+        None => self.back = Some(first),
+      }
+      self.bump_generation();
     }
   }
-}
 
-#[cfg(test)]
-mod test
-{
-  use super::{List, Node};
-  use core::{
-    alloc::{GlobalAlloc, Layout},
-    ptr::NonNull,
-  };
-  use std::alloc::System;
+  // Reverses the order of the nodes in the inclusive index range [i, j].
+  // Panics unless i <= j < len.
+  pub fn reverse_between(&mut self, i: usize, j: usize)
+  {
+    assert!(
+      i <= j && j < self.len,
+      "reverse_between range [{}, {}] is invalid for len {}",
+      i,
+      j,
+      self.len
+    );
+    if i == j
+    {
+      return;
+    }
+    let first = self.node_at(i).unwrap();
+    let last = self.node_at(j).unwrap();
+    unsafe { self.reverse_chain(first, last) };
+  }
 
-  type NodeType = Node<usize>;
-  const NODE_LAYOUT: Layout =
-    unsafe { Layout::from_size_align_unchecked(size_of::<NodeType>(), align_of::<NodeType>()) };
+  // Returns the node where the next links start looping back on themselves, or None if they don't.
+  // Meant for debugging manual node surgery. The walk gives up (returning None) after 2 * len steps,
+  // so a list whose len is also wrong won't spin forever.
+  pub fn detect_cycle(&self) -> Link<T>
+  {
+    let cap = 2 * self.len;
+    let next = |x: NonNull<Node<T>>| unsafe { (*x.as_ptr()).next };
 
-  #[test]
-  fn test_basic_front()
+    // Phase one, find a meeting point inside the cycle
+    let mut slow = self.front?;
+    let mut fast = self.front?;
+    let mut steps = 0;
+    loop
+    {
+      fast = next(next(fast)?)?;
+      slow = next(slow)?;
+      if slow == fast
+      {
+        break;
+      }
+      steps += 1;
+      if steps > cap
+      {
+        return None;
+      }
+    }
+
+    // Phase two, a pointer from the front and one from the meeting point meet at the entry
+    let mut from_front = self.front?;
+    while from_front != slow
+    {
+      from_front = next(from_front)?;
+      slow = next(slow)?;
+      steps += 1;
+      if steps > 2 * cap
+      {
+        return None;
+      }
+    }
+    Some(from_front)
+  }
+
+  // Points whatever comes before a slot (prev, or the front if there is none) at node.
+  pub(self) unsafe fn set_next_of(&mut self, prev: Link<T>, node: Link<T>)
   {
-    let mut list = List::new();
+    match prev
+    {
+      Some(p) =>
+      unsafe { (*p.as_ptr()).next = node },
+      None => self.front = node,
+    }
+  }
 
-    // Try to break an empty list
-    assert_eq!(list.len(), 0);
-    assert_eq!(list.pop_front(), None);
-    assert_eq!(list.len(), 0);
+  // Points whatever comes after a slot (next, or the back if there is none) at node.
+  pub(self) unsafe fn set_prev_of(&mut self, next: Link<T>, node: Link<T>)
+  {
+    match next
+    {
+      Some(n) =>
+      unsafe { (*n.as_ptr()).prev = node },
+      None => self.back = node,
+    }
+  }
 
+  // Swaps the positions of a and b by relinking, the elements stay in their nodes.
+  // Both nodes have to be in this list.
+  pub fn swap_nodes(&mut self, a: NonNull<Node<T>>, b: NonNull<Node<T>>)
+  {
+    if a == b
+    {
+      return;
+    }
     unsafe {
-      let ten = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
-      let twe = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
-      let thr = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
-      let frt = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
-      ten.write(Node::new(10));
-      twe.write(Node::new(20));
-      thr.write(Node::new(30));
-      frt.write(Node::new(40));
-      // Try to break a one item list
-      list.push_front(ten);
-      assert_eq!(list.len(), 1);
-      assert_eq!(list.pop_front(), Some(ten));
-      assert_eq!(list.len(), 0);
-      assert_eq!(list.pop_front(), None);
-      assert_eq!(list.len(), 0);
+      let (a_prev, a_next) = ((*a.as_ptr()).prev, (*a.as_ptr()).next);
+      let (b_prev, b_next) = ((*b.as_ptr()).prev, (*b.as_ptr()).next);
 
-      // Mess around
-      list.push_front(ten);
-      assert_eq!(list.len(), 1);
-      list.push_front(twe);
-      assert_eq!(list.len(), 2);
-      list.push_front(thr);
-      assert_eq!(list.len(), 3);
-      assert_eq!(list.pop_front(), Some(thr));
-      assert_eq!(list.len(), 2);
-      list.push_front(frt);
-      assert_eq!(list.len(), 3);
-      assert_eq!(list.pop_front(), Some(frt));
-      assert_eq!(list.len(), 2);
-      assert_eq!(list.pop_front(), Some(twe));
-      assert_eq!(list.len(), 1);
-      assert_eq!(list.pop_front(), Some(ten));
-      assert_eq!(list.len(), 0);
-      assert_eq!(list.pop_front(), None);
-      assert_eq!(list.len(), 0);
-      assert_eq!(list.pop_front(), None);
-      assert_eq!(list.len(), 0);
+      if a_next == Some(b)
+      {
+        // Neighbours, a_prev <-> a <-> b <-> b_next becomes a_prev <-> b <-> a <-> b_next
+        (*b.as_ptr()).prev = a_prev;
+        (*b.as_ptr()).next = Some(a);
+        (*a.as_ptr()).prev = Some(b);
+        (*a.as_ptr()).next = b_next;
+        self.set_next_of(a_prev, Some(b));
+        self.set_prev_of(b_next, Some(a));
+      }
+      else if b_next == Some(a)
+      {
+        // Same as above with the roles flipped
+        self.swap_nodes(b, a);
+        return;
+      }
+      else
+      {
+        (*a.as_ptr()).prev = b_prev;
+        (*a.as_ptr()).next = b_next;
+        (*b.as_ptr()).prev = a_prev;
+        (*b.as_ptr()).next = a_next;
+        self.set_next_of(a_prev, Some(b));
+        self.set_prev_of(a_next, Some(b));
+        self.set_next_of(b_prev, Some(a));
+        self.set_prev_of(b_next, Some(a));
+      }
+      self.bump_generation();
+    }
+  }
 
-      System.dealloc(ten.as_ptr() as *mut u8, NODE_LAYOUT);
-      System.dealloc(twe.as_ptr() as *mut u8, NODE_LAYOUT);
-      System.dealloc(thr.as_ptr() as *mut u8, NODE_LAYOUT);
-      System.dealloc(frt.as_ptr() as *mut u8, NODE_LAYOUT);
+  // True if in_order holds for every adjacent pair front to back, empty and single lists always are.
+  pub fn is_sorted_by<F: FnMut(&T, &T) -> bool>(&self, mut in_order: F) -> bool
+  {
+    if self.len < 2
+    {
+      return true;
+    }
+    let mut node = self.front;
+    unsafe {
+      while let Some(x) = node
+      {
+        let Some(next) = (*x.as_ptr()).next
+        else
+        {
+          break;
+        };
+        if !in_order(&(*x.as_ptr()).elem, &(*next.as_ptr()).elem)
+        {
+          return false;
+        }
+        node = Some(next);
+      }
     }
+    true
   }
 
-  #[test]
-  fn test_cursor_remove()
+  // Mutable access to the elements at two different indices at once.
+  // None if i == j or either index is out of range.
+  pub fn get_two_mut(&mut self, i: usize, j: usize) -> Option<(&mut T, &mut T)>
   {
-    const COUNT: usize = 100;
-    let mut alloc_vec = Vec::with_capacity(COUNT);
-    let mut list = List::new();
-    for i in 0..COUNT
+    if i == j
     {
-      let ptr = unsafe {
-        let ret = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
-        ret.as_ptr().write(Node::new(i));
-        ret
+      return None;
+    }
+    let a = self.node_at(i)?;
+    let b = self.node_at(j)?;
+    // Different indices means different nodes, so the two references never alias
+    unsafe { Some((&mut (*a.as_ptr()).elem, &mut (*b.as_ptr()).elem)) }
+  }
+
+  // Exchanges the contents of two lists in O(1).
+  pub fn swap_lists(a: &mut List<T>, b: &mut List<T>)
+  {
+    core::mem::swap(&mut a.front, &mut b.front);
+    core::mem::swap(&mut a.back, &mut b.back);
+    core::mem::swap(&mut a.len, &mut b.len);
+    a.bump_generation();
+    b.bump_generation();
+  }
+
+  // Links node in right before at, or at the back when at is None. at has to be in this list.
+  pub(self) unsafe fn link_before(&mut self, at: Link<T>, node: NonNull<Node<T>>)
+  {
+    let Some(at) = at
+    else
+    {
+      self.push_back(node);
+      return;
+    };
+    unsafe {
+      let prev = (*at.as_ptr()).prev;
+      (*node.as_ptr()).prev = prev;
+      (*node.as_ptr()).next = Some(at);
+      (*at.as_ptr()).prev = Some(node);
+      self.set_next_of(prev, Some(node));
+    }
+    self.len += 1;
+    self.bump_generation();
+  }
+
+  // Inserts node before the first element that compares greater than it, keeping a sorted list sorted.
+  // Equal elements stay in insertion order. The list has to already be sorted by cmp.
+  pub fn insert_sorted<F: FnMut(&T, &T) -> Ordering>(&mut self, node: NonNull<Node<T>>, mut cmp: F)
+  {
+    let elem = unsafe { &(*node.as_ptr()).elem };
+    let at = self.first_node_where(|x| cmp(x, elem) == Ordering::Greater);
+    unsafe { self.link_before(at, node) };
+  }
+
+  // Mutable access to both ends at once. With a single element it is only handed out once, as the
+  // front, and the back is None.
+  pub fn front_back_mut(&mut self) -> (Option<&mut T>, Option<&mut T>)
+  {
+    unsafe {
+      let front = self.front.map(|x| &mut (*x.as_ptr()).elem);
+      let back = match self.len
+      {
+        0 | 1 => None,
+        _ => self.back.map(|x| &mut (*x.as_ptr()).elem),
       };
-      list.push_front(ptr);
-      alloc_vec.push(ptr);
+      (front, back)
     }
+  }
 
-    let mut counter = 0;
-    let start_idx = rand::random_range(0..list.len());
-    let mut cursor = list.cursor_mut();
-    cursor.move_next();
-    for _ in 0..start_idx
+  // Index and node of the first element matching pred, walking from the front.
+  pub fn position<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Option<(usize, NonNull<Node<T>>)>
+  {
+    let mut node = self.front;
+    let mut idx = 0;
+    unsafe {
+      while let Some(x) = node
+      {
+        if pred(&(*x.as_ptr()).elem)
+        {
+          return Some((idx, x));
+        }
+        node = (*x.as_ptr()).next;
+        idx += 1;
+      }
+    }
+    None
+  }
+
+  // Index (counted from the front) and node of the last element matching pred, walking from the back.
+  pub fn rposition<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Option<(usize, NonNull<Node<T>>)>
+  {
+    let mut node = self.back;
+    let mut steps_from_back = 0;
+    unsafe {
+      while let Some(x) = node
+      {
+        if pred(&(*x.as_ptr()).elem)
+        {
+          return Some((self.len - 1 - steps_from_back, x));
+        }
+        node = (*x.as_ptr()).prev;
+        steps_from_back += 1;
+      }
+    }
+    None
+  }
+
+  // Builds a new list out of f applied to every element, each new node is its own Box.
+  // The source list isn't touched.
+  #[cfg(feature = "alloc")]
+  pub fn map<U, F: FnMut(&T) -> U>(&self, mut f: F) -> List<U>
+  {
+    let mut ret = List::new();
+    let mut node = self.front;
+    while let Some(x) = node
     {
-      cursor.move_next();
+      unsafe {
+        let new = NonNull::from(Box::leak(Box::new(Node::new(f(&(*x.as_ptr()).elem)))));
+        ret.push_back(new);
+        node = (*x.as_ptr()).next;
+      }
     }
+    ret
+  }
 
-    while cursor.current_value().is_some()
+  // Empties the list, walking it so every node comes out with its links reset.
+  pub fn clear(&mut self)
+  {
+    while self.pop_front().is_some()
+    {}
+  }
+
+  // Empties the list in O(1) without touching the nodes, handing back the old front and back.
+  // The orphaned nodes keep their stale links to each other, which is fine for a pool that overwrites
+  // them anyway but means the cursor inserts will reject them (push_front and push_back reset links).
+  // Walk from the returned front to free them, or use clear if they need to come out clean.
+  pub fn clear_fast(&mut self) -> (Link<T>, Link<T>)
+  {
+    let ends = (self.front.take(), self.back.take());
+    self.len = 0;
+    self.bump_generation();
+    ends
+  }
+
+  // Every node pointer front to back.
+  pub fn iter_nodes(&self) -> impl Iterator<Item = NonNull<Node<T>>>
+  {
+    core::iter::successors(self.front, |x| unsafe { (*x.as_ptr()).next })
+  }
+
+  // Where node sits in this list, or None if it isn't in it.
+  pub fn node_index(&self, node: NonNull<Node<T>>) -> Option<usize>
+  {
+    self.iter_nodes().position(|x| x == node)
+  }
+
+  // Keeps only the elements pred returns true for, the rest are unlinked and returned in order.
+  pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> List<T>
+  {
+    self.retain_mut(|x| pred(x))
+  }
+
+  // retain, but pred gets to modify each element while deciding on it.
+  pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut pred: F) -> List<T>
+  {
+    let mut removed = List::new();
+    let mut node = self.front;
+    while let Some(x) = node
     {
-      cursor.remove();
-      counter += 1;
+      unsafe {
+        node = (*x.as_ptr()).next;
+        if !pred(&mut (*x.as_ptr()).elem)
+        {
+          self.unlink_range(x, x, 1);
+          removed.push_back(x);
+        }
+      }
     }
+    removed
+  }
 
-    drop(cursor);
-    while list.pop_front().is_some()
+  // Lets the first n elements matching pred stay, along with everything that doesn't match. Matches
+  // past the nth are unlinked and returned in order.
+  pub fn retain_first_matches<F: FnMut(&T) -> bool>(&mut self, mut n: usize, mut pred: F)
+  -> List<T>
+  {
+    self.retain(|x| {
+      if !pred(x)
+      {
+        return true;
+      }
+      let keep = n > 0;
+      n = n.saturating_sub(1);
+      keep
+    })
+  }
+
+  pub fn iter(&self) -> Iter<'_, T>
+  {
+    Iter {
+      current: self.front,
+      remaining: self.len,
+      _phantom: PhantomData,
+    }
+  }
+
+  pub fn iter_mut(&mut self) -> IterMut<'_, T>
+  {
+    IterMut {
+      current: self.front,
+      remaining: self.len,
+      _phantom: PhantomData,
+    }
+  }
+
+  // Pops from the back for as long as the back element matches pred, handing each node to sink.
+  pub fn drain_back_while<F: FnMut(&T) -> bool>(
+    &mut self,
+    mut pred: F,
+    mut sink: impl FnMut(NonNull<Node<T>>),
+  )
+  {
+    while self.back_val().is_some_and(&mut pred)
     {
-      counter += 1;
+      if let Some(node) = self.pop_back()
+      {
+        sink(node);
+      }
     }
-    assert!(counter == COUNT);
+  }
 
-    while let Some(x) = alloc_vec.pop()
+  /// Rebuilds every `prev` link, `back` and `len` from the `next` links starting at `front`.
+  /// Handy after porting singly linked code that only maintains `next`.
+  ///
+  /// # Safety
+  /// The chain of `next` links from `front` must be made of valid nodes and end in `None`, no cycles.
+  pub unsafe fn repair_from_front(&mut self)
+  {
+    let mut prev: Link<T> = None;
+    let mut node = self.front;
+    let mut len = 0;
+    while let Some(x) = node
     {
-      unsafe { System.dealloc(x.as_ptr() as *mut u8, NODE_LAYOUT) };
+      unsafe {
+        (*x.as_ptr()).prev = prev;
+        node = (*x.as_ptr()).next;
+      }
+      prev = Some(x);
+      len += 1;
     }
+    self.back = prev;
+    self.len = len;
+    self.bump_generation();
   }
 
-  #[test]
-  fn test_cursor_insert_before()
+  // How many elements come before the first one matching pred, or None if nothing matches.
+  pub fn count_until<F: FnMut(&T) -> bool>(&self, pred: F) -> Option<usize>
   {
-    const COUNT: usize = 100;
-    const INSERT_MAX: usize = 20;
-    let mut alloc_vec = Vec::with_capacity(COUNT);
-    let mut list = List::new();
-    for i in 0..COUNT
+    self.iter().position(pred)
+  }
+
+  // Folds the elements together front to back with f, seeded by the first element. f builds each
+  // new T from two references so T doesn't need to be Clone, which leaves nothing to hand back by
+  // value for a single element: that comes out as Err with a reference to it. None when empty.
+  pub fn reduce<F: FnMut(&T, &T) -> T>(&self, mut f: F) -> Option<Result<T, &T>>
+  {
+    let mut iter = self.iter();
+    let first = iter.next()?;
+    let Some(second) = iter.next()
+    else
     {
-      let ptr = unsafe {
-        let ret = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
-        ret.as_ptr().write(Node::new(i));
-        ret
-      };
-      list.push_front(ptr);
-      alloc_vec.push(ptr);
+      return Some(Err(first));
+    };
+    let mut acc = f(first, second);
+    for x in iter
+    {
+      acc = f(&acc, x);
     }
+    Some(Ok(acc))
+  }
 
-    let start_idx = rand::random_range(0..list.len());
-    let mut cursor = list.cursor_mut();
-    cursor.move_next();
-    for _ in 0..start_idx
+  // True if both lists hold the same elements the same number of times, in any order.
+  // With alloc this sorts references to both sides, without it every element's count is compared
+  // by walking both lists, which is O(n^2) but needs no memory.
+  pub fn multiset_eq(&self, other: &List<T>) -> bool
+  where
+    T: Ord,
+  {
+    if self.len != other.len
     {
-      cursor.move_next();
+      return false;
     }
-    let insert_count = rand::random_range(1..INSERT_MAX);
-    for i in 0..insert_count
+
+    #[cfg(feature = "alloc")]
     {
-      let ptr = unsafe {
-        let ret = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
-        ret.as_ptr().write(Node::new(i));
-        ret
-      };
-      cursor.insert_before(ptr);
-      alloc_vec.push(ptr);
+      let mut a = self.collect_refs();
+      let mut b = other.collect_refs();
+      a.sort_unstable();
+      b.sort_unstable();
+      a == b
     }
-    drop(cursor);
-    dbg!(list.len());
-    dbg!(insert_count);
-    assert!(list.len() == COUNT + insert_count);
 
-    while let Some(x) = alloc_vec.pop()
+    #[cfg(not(feature = "alloc"))]
     {
-      unsafe { System.dealloc(x.as_ptr() as *mut u8, NODE_LAYOUT) };
+      self.iter().all(|x| {
+        let count = |list: &List<T>| list.iter().filter(|y| x.cmp(y) == Ordering::Equal).count();
+        count(self) == count(other)
+      })
+    }
+  }
+
+  // Moves all of other's nodes in right before at (or onto the back when at is None), leaving other
+  // empty. O(1), at has to be in this list.
+  pub(self) unsafe fn splice_before(&mut self, at: Link<T>, other: &mut List<T>)
+  {
+    let (Some(first), Some(last)) = (other.front, other.back)
+    else
+    {
+      return;
+    };
+    unsafe {
+      let prev = match at
+      {
+        Some(a) => (*a.as_ptr()).prev,
+        None => self.back,
+      };
+      (*first.as_ptr()).prev = prev;
+      (*last.as_ptr()).next = at;
+      self.set_next_of(prev, Some(first));
+      self.set_prev_of(at, Some(last));
+    }
+    self.len += other.len;
+    self.bump_generation();
+    other.clear_fast();
+  }
+
+  // Zips other's nodes in between this list's, one after each of ours, starting with ours. Whatever is
+  // left of other once we run out goes onto the back, other ends up empty. No allocation, just relinks.
+  pub fn interleave(&mut self, other: &mut List<T>)
+  {
+    let mut node = self.front;
+    while let Some(x) = node
+    {
+      let Some(theirs) = other.pop_front()
+      else
+      {
+        return;
+      };
+      unsafe {
+        let next = (*x.as_ptr()).next;
+        self.link_before(next, theirs);
+        node = next;
+      }
+    }
+    unsafe { self.splice_before(None, other) };
+  }
+
+  // insert_sorted comparing the keys key_fn pulls out of each element. The new node's key is only
+  // worked out once.
+  pub fn insert_sorted_by_key<K: Ord, F: FnMut(&T) -> K>(
+    &mut self,
+    node: NonNull<Node<T>>,
+    mut key_fn: F,
+  )
+  {
+    let key = key_fn(unsafe { &(*node.as_ptr()).elem });
+    let at = self.first_node_where(|x| key_fn(x) > key);
+    unsafe { self.link_before(at, node) };
+  }
+
+  // Cuts the whole list into consecutive pages of n nodes each (the last one may be shorter), leaving
+  // this list empty. Panics if n is 0.
+  #[cfg(feature = "alloc")]
+  pub fn split_every(&mut self, n: usize) -> Vec<List<T>>
+  {
+    assert!(n > 0, "split_every needs a page size of at least 1");
+    let mut pages = Vec::with_capacity(self.len.div_ceil(n));
+    while let Some(first) = self.front
+    {
+      let count = n.min(self.len);
+      let mut last = first;
+      for _ in 1..count
+      {
+        last = unsafe { (*last.as_ptr()).next.unwrap() };
+      }
+      pages.push(unsafe { self.unlink_range(first, last, count) });
+    }
+    pages
+  }
+
+  // Rotates the list so node becomes the front, keeping the cyclic order of everything else. O(1),
+  // node has to be in this list.
+  pub fn rotate_to_node(&mut self, node: NonNull<Node<T>>)
+  {
+    let (Some(front), Some(back)) = (self.front, self.back)
+    else
+    {
+      return;
+    };
+    if node == front
+    {
+      return;
+    }
+    unsafe {
+      // Close the ring, then cut it right before node
+      (*back.as_ptr()).next = Some(front);
+      (*front.as_ptr()).prev = Some(back);
+      let new_back = (*node.as_ptr()).prev;
+      (*node.as_ptr()).prev = None;
+      if let Some(b) = new_back
+      {
+        (*b.as_ptr()).next = None;
+      }
+      self.front = Some(node);
+      self.back = new_back;
+    }
+    self.bump_generation();
+  }
+
+  // Folds over the node pointers front to back, e.g. to build an index of them in one pass.
+  pub fn fold_nodes<B, F: FnMut(B, NonNull<Node<T>>) -> B>(&self, init: B, f: F) -> B
+  {
+    self.iter_nodes().fold(init, f)
+  }
+
+  // Whether node is linked into this list, checked by walking it. Worth doing before handing a node
+  // to one of the node based removals.
+  pub fn contains_node(&self, node: NonNull<Node<T>>) -> bool
+  {
+    self.iter_nodes().any(|x| x == node)
+  }
+
+  // Indices of every element matching pred, in one walk.
+  #[cfg(feature = "alloc")]
+  pub fn indices_where<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Vec<usize>
+  {
+    self
+      .iter()
+      .enumerate()
+      .filter_map(|(i, x)| pred(x).then_some(i))
+      .collect()
+  }
+
+  // Removes every element that already showed up earlier in the list, not just consecutive repeats,
+  // returning them in order. First occurrences keep their places. Uses a BTreeSet since there is no
+  // HashSet without std, hence Ord rather than Hash + Eq.
+  #[cfg(feature = "alloc")]
+  pub fn dedup_all(&mut self) -> List<T>
+  where
+    T: Ord,
+  {
+    let mut seen = BTreeSet::new();
+    let mut removed = List::new();
+    let mut node = self.front;
+    while let Some(x) = node
+    {
+      unsafe {
+        node = (*x.as_ptr()).next;
+        // Only nodes that stay in the list end up in seen, so these references stay valid
+        if !seen.insert(&(*x.as_ptr()).elem)
+        {
+          self.unlink_range(x, x, 1);
+          removed.push_back(x);
+        }
+      }
+    }
+    removed
+  }
+
+  // slice::binary_search_by over a list sorted per f: the index and node of a match, or the index
+  // to insert at on a miss. Each probe is a node_at walk, so O(log n) probes but O(n log n) steps.
+  pub fn binary_search_by<F: FnMut(&T) -> Ordering>(
+    &self,
+    mut f: F,
+  ) -> Result<(usize, NonNull<Node<T>>), usize>
+  {
+    let (mut lo, mut hi) = (0, self.len);
+    while lo < hi
+    {
+      let mid = lo + (hi - lo) / 2;
+      let node = self.node_at(mid).unwrap();
+      match f(unsafe { &(*node.as_ptr()).elem })
+      {
+        Ordering::Less => lo = mid + 1,
+        Ordering::Greater => hi = mid,
+        Ordering::Equal => return Ok((mid, node)),
+      }
+    }
+    Err(lo)
+  }
+
+  // map that reuses the nodes: each T is read out and f's U written back into the same storage, then
+  // the whole chain is reinterpreted as a List<U>. T and U must match in size and alignment, which
+  // with Node being repr(C) makes Node<T> and Node<U> interchangeable, so the nodes can be freed the
+  // same way they were allocated. If f panics the node it was working on is left holding a moved-out
+  // T, so don't drop that one.
+  pub fn map_in_place_same_size<U, F: FnMut(T) -> U>(self, mut f: F) -> List<U>
+  {
+    assert!(
+      size_of::<T>() == size_of::<U>() && align_of::<T>() == align_of::<U>(),
+      "map_in_place_same_size needs T and U with the same layout"
+    );
+    let mut node = self.front;
+    while let Some(x) = node
+    {
+      unsafe {
+        node = (*x.as_ptr()).next;
+        let elem = &raw mut (*x.as_ptr()).elem;
+        let value = f(elem.read());
+        elem.cast::<U>().write(value);
+      }
+    }
+    List {
+      front: self.front.map(NonNull::cast),
+      back: self.back.map(NonNull::cast),
+      len: self.len,
+      #[cfg(debug_assertions)]
+      generation: self.generation,
+      _phantom: PhantomData,
+    }
+  }
+
+  // Front and back elements in one go, the same element twice for a single-node list.
+  pub fn ends(&self) -> Option<(&T, &T)>
+  {
+    Some((self.front_val()?, self.back_val()?))
+  }
+
+  // Splits off everything from the first element matching pred onwards, the match included, or
+  // returns None and leaves the list alone if nothing matches.
+  pub fn split_at_first<F: FnMut(&T) -> bool>(&mut self, pred: F) -> Option<List<T>>
+  {
+    let (idx, node) = self.position(pred)?;
+    Some(unsafe { self.unlink_range(node, self.back?, self.len - idx) })
+  }
+
+  // Rotates n places in dir, n wrapping around len. Finding the new front is a node_at walk, the
+  // rotation itself is rotate_to_node's O(1) relink.
+  pub fn rotate(&mut self, dir: RotateDir, n: usize)
+  {
+    if self.len < 2
+    {
+      return;
+    }
+    let n = n % self.len;
+    let new_front = match dir
+    {
+      RotateDir::Left => n,
+      RotateDir::Right => (self.len - n) % self.len,
+    };
+    if let Some(node) = self.node_at(new_front)
+    {
+      self.rotate_to_node(node);
+    }
+  }
+
+  // The smallest and largest elements in one pass. Ties go the same way as Iterator::min and max,
+  // the first of several minimums and the last of several maximums.
+  pub fn min_max(&self) -> Option<(&T, &T)>
+  where
+    T: Ord,
+  {
+    let mut iter = self.iter();
+    let first = iter.next()?;
+    Some(iter.fold((first, first), |(min, max), x| {
+      (
+        if x < min { x } else { min },
+        if x >= max { x } else { max },
+      )
+    }))
+  }
+
+  // Relinks the nodes so that position i ends up holding what was at index perm[i], the elements stay
+  // in their nodes. Panics unless perm is a permutation of 0..len.
+  #[cfg(feature = "alloc")]
+  pub fn apply_permutation(&mut self, perm: &[usize])
+  {
+    assert_eq!(
+      perm.len(),
+      self.len,
+      "apply_permutation needs one index per node"
+    );
+    let mut seen = Vec::new();
+    seen.resize(self.len, false);
+    for &i in perm
+    {
+      assert!(
+        i < self.len && !seen[i],
+        "apply_permutation got {:?}, which isn't a permutation of 0..{}",
+        perm,
+        self.len
+      );
+      seen[i] = true;
+    }
+
+    let nodes: Vec<_> = self.iter_nodes().collect();
+    unsafe { self.relink_in_order(perm.iter().map(|&i| nodes[i])) };
+  }
+
+  // Throws away the current links and chains nodes up front to back in the order given. nodes has to
+  // yield every node of this list exactly once.
+  #[cfg(feature = "alloc")]
+  pub(self) unsafe fn relink_in_order<I: IntoIterator<Item = NonNull<Node<T>>>>(&mut self, nodes: I)
+  {
+    self.clear_fast();
+    self.extend(nodes);
+  }
+
+  // Stable sort by the keys key_fn pulls out, relinking the nodes rather than moving elements. Each
+  // key is worked out once and cached next to its node, then the pairs go through slice's stable sort.
+  #[cfg(feature = "alloc")]
+  pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key_fn: F)
+  {
+    let mut keyed: Vec<_> = self
+      .iter_nodes()
+      .map(|x| (key_fn(unsafe { &(*x.as_ptr()).elem }), x))
+      .collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    unsafe { self.relink_in_order(keyed.into_iter().map(|(_, x)| x)) };
+  }
+
+  // Vec::swap_remove by relinking: the back node is moved into index's place and the node that was
+  // there is unlinked and returned, so every node keeps its element. None if index is out of range.
+  pub fn swap_remove(&mut self, index: usize) -> Link<T>
+  {
+    let node = self.node_at(index)?;
+    let back = self.back?;
+    self.swap_nodes(node, back);
+    self.pop_back()
+  }
+
+  // slice::chunks for a list: groups of n element references front to back, the last one may be
+  // shorter. Nothing is unlinked. Panics if n is 0.
+  #[cfg(feature = "alloc")]
+  pub fn chunks(&self, n: usize) -> impl Iterator<Item = Vec<&T>>
+  {
+    assert!(n > 0, "chunks needs a chunk size of at least 1");
+    let mut iter = self.iter();
+    core::iter::from_fn(move || {
+      let chunk: Vec<_> = iter.by_ref().take(n).collect();
+      (!chunk.is_empty()).then_some(chunk)
+    })
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> List<List<T>>
+{
+  /// Concatenates every inner list in order into one, freeing each outer node once its list has been
+  /// emptied out.
+  ///
+  /// # Safety
+  /// Every outer node must have come from `Box::into_raw` (or Box::leak) of a `Box<Node<List<T>>>`.
+  pub unsafe fn flatten(mut self) -> List<T>
+  {
+    let mut ret = List::new();
+    while let Some(node) = self.pop_front()
+    {
+      unsafe {
+        let mut outer = Box::from_raw(node.as_ptr());
+        ret.splice_before(None, &mut outer.elem);
+      }
+    }
+    ret
+  }
+}
+
+impl<T> Default for List<T>
+{
+  fn default() -> Self
+  {
+    Self::new()
+  }
+}
+
+impl<T> Extend<NonNull<Node<T>>> for List<T>
+{
+  fn extend<I: IntoIterator<Item = NonNull<Node<T>>>>(&mut self, iter: I)
+  {
+    for node in iter
+    {
+      self.push_back(node);
+    }
+  }
+}
+
+impl<T> FromIterator<NonNull<Node<T>>> for List<T>
+{
+  fn from_iter<I: IntoIterator<Item = NonNull<Node<T>>>>(iter: I) -> Self
+  {
+    let mut list = List::new();
+    list.extend(iter);
+    list
+  }
+}
+
+impl<'a, T> Iterator for Iter<'a, T>
+{
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item>
+  {
+    self.current.map(|x| unsafe {
+      self.current = (*x.as_ptr()).next;
+      self.remaining -= 1;
+      &(*x.as_ptr()).elem
+    })
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>)
+  {
+    (self.remaining, Some(self.remaining))
+  }
+
+  // Skips straight over the links instead of building a reference for every skipped node
+  fn nth(&mut self, n: usize) -> Option<Self::Item>
+  {
+    if n >= self.remaining
+    {
+      self.current = None;
+      self.remaining = 0;
+      return None;
+    }
+    for _ in 0..n
+    {
+      self.current = self.current.and_then(|x| unsafe { (*x.as_ptr()).next });
+    }
+    self.remaining -= n;
+    self.next()
+  }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+// Manual impl, deriving would needlessly require T: Clone
+impl<T> Clone for Iter<'_, T>
+{
+  fn clone(&self) -> Self
+  {
+    Self {
+      current: self.current,
+      remaining: self.remaining,
+      _phantom: PhantomData,
+    }
+  }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T>
+{
+  type Item = &'a mut T;
+
+  fn next(&mut self) -> Option<Self::Item>
+  {
+    self.current.map(|x| unsafe {
+      // Step past the node before handing out its element, so nothing we keep points at it
+      self.current = (*x.as_ptr()).next;
+      self.remaining -= 1;
+      &mut (*x.as_ptr()).elem
+    })
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>)
+  {
+    (self.remaining, Some(self.remaining))
+  }
+
+  fn nth(&mut self, n: usize) -> Option<Self::Item>
+  {
+    if n >= self.remaining
+    {
+      self.current = None;
+      self.remaining = 0;
+      return None;
+    }
+    for _ in 0..n
+    {
+      self.current = self.current.and_then(|x| unsafe { (*x.as_ptr()).next });
+    }
+    self.remaining -= n;
+    self.next()
+  }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> Node<T>
+{
+  pub fn new(data: T) -> Self
+  {
+    Self {
+      next: None,
+      prev: None,
+      elem: data,
+    }
+  }
+  pub fn elem(&self) -> &T
+  {
+    &self.elem
+  }
+  pub fn elem_mut(&mut self) -> &mut T
+  {
+    &mut self.elem
+  }
+
+  // Puts new in and hands back the old element.
+  pub fn replace_elem(&mut self, new: T) -> T
+  {
+    core::mem::replace(&mut self.elem, new)
+  }
+
+  pub fn next_node(&self) -> Link<T>
+  {
+    self.next
+  }
+  pub fn prev_node(&self) -> Link<T>
+  {
+    self.prev
+  }
+
+  // (prev, next) in one go
+  pub fn links(&self) -> (Link<T>, Link<T>)
+  {
+    (self.prev, self.next)
+  }
+
+  fn is_linked(&self) -> bool
+  {
+    self.next.is_some() || self.prev.is_some()
+  }
+}
+
+impl<'a, T> CursorMut<'a, T>
+{
+  pub(self) fn new(list: &'a mut List<T>) -> Self
+  {
+    #[cfg(debug_assertions)]
+    let generation = list.generation;
+    Self {
+      idx: None,
+      current: None,
+      list,
+      #[cfg(debug_assertions)]
+      generation,
+    }
+  }
+
+  // Catches cursor methods in this crate that change the list's structure without calling mutated.
+  // The cursor borrows the list mutably, so nothing outside the crate can get at it in the meantime,
+  // this only guards against our own bugs. Debug builds only.
+  fn check_generation(&self)
+  {
+    #[cfg(debug_assertions)]
+    assert!(
+      self.generation == self.list.generation,
+      "cursor used after its list was structurally modified behind its back"
+    );
+  }
+
+  // Called after the cursor itself changes the list's structure, so it doesn't trip its own check
+  fn mutated(&mut self)
+  {
+    self.list.bump_generation();
+    #[cfg(debug_assertions)]
+    {
+      self.generation = self.list.generation;
+    }
+  }
+
+  pub fn move_next(&mut self)
+  {
+    self.check_generation();
+    if let Some(node) = self.current
+    {
+      self.current = unsafe { (*node.as_ptr()).next };
+      if self.current.is_some()
+      {
+        // An unknown index stays unknown until the cursor passes through the ghost
+        self.idx = self.idx.map(|x| x + 1);
+      }
+      else
+      {
+        self.idx = None;
+      }
+    }
+    else
+    {
+      self.idx = self.list.front.map(|_| 0);
+      self.current = self.list.front;
+    }
+  }
+  pub fn move_prev(&mut self)
+  {
+    self.check_generation();
+    if let Some(node) = self.current
+    {
+      self.current = unsafe { (*node.as_ptr()).prev };
+      if self.current.is_some()
+      {
+        // A node with a prev can't be at index 0, checked_sub keeps a bad idx from wrapping to usize::MAX
+        self.idx = self.idx.and_then(|x| x.checked_sub(1));
+      }
+      else
+      {
+        // Stepped off the front, back onto the ghost
+        self.idx = None;
+      }
+    }
+    else
+    {
+      // On an empty list there is no back to move to, so stay on the ghost
+      self.idx = self.list.len_minus_one();
+      self.current = self.list.back;
+    }
+  }
+
+  pub fn current_value(&mut self) -> Option<&mut T>
+  {
+    self.check_generation();
+    self.current.map(|x| unsafe { &mut (*x.as_ptr()).elem })
+  }
+
+  pub fn current_link(&mut self) -> Link<T>
+  {
+    self.current
+  }
+
+  // Unlinks the current node and moves on to the one after it. See remove_stay for a version that
+  // doesn't move forward.
+  pub fn remove(&mut self) -> Link<T>
+  {
+    let link_current = self.current;
+    self.move_next();
+
+    unsafe {
+      if let Some(p_current) = link_current
+      {
+        self.idx = self.idx.and_then(|x| x.checked_sub(1));
+
+        let current = &mut (*p_current.as_ptr());
+        let (o_prev, o_next) = (current.prev, current.next);
+
+        let removed = match (o_prev, o_next)
+        {
+          (None, None) => self.list.pop_front(), // Only node in the list, therefore pop front will do the trick
+          (None, Some(_)) => self.list.pop_front(), // its the front node
+          (Some(_), None) => self.list.pop_back(), // its the back node
+          (Some(p), Some(n)) =>
+          {
+            // Its the middle node, stitch the two nodes together
+            (*p.as_ptr()).next = Some(n);
+            (*n.as_ptr()).prev = Some(p);
+            self.list.len -= 1;
+            current.prev = None;
+            current.next = None;
+            link_current
+          }
+        };
+        self.mutated();
+        removed
+      }
+      else
+      {
+        link_current
+      }
+    }
+  }
+
+  // The list takes over node's position, the caller must not free it while it is still linked in.
+  // node has to be free standing, in debug builds a node that still has links panics.
+  pub fn insert_before(&mut self, node: NonNull<Node<T>>)
+  {
+    debug_assert!(
+      unsafe { !node.as_ref().is_linked() },
+      "inserted a node that is still linked into a list"
+    );
+    let link_current = self.current;
+    unsafe {
+      if let Some(p_current) = link_current
+      {
+        let current = &mut (*p_current.as_ptr());
+        if let Some(p_prev) = current.prev
+        {
+          let prev = &mut (*p_prev.as_ptr());
+          let new = &mut (*node.as_ptr());
+          prev.next = Some(node);
+          current.prev = Some(node);
+
+          new.prev = Some(p_prev);
+          new.next = Some(p_current);
+          self.idx = self.idx.map(|x| x + 1);
+          self.list.len += 1;
+        }
+        // at front
+        else
+        {
+          self.list.push_front(node);
+          self.idx = self.idx.map(|x| x + 1);
+        }
+      }
+      // The ghost sits between the back and the front, so before it is the back
+      else
+      {
+        self.list.push_back(node);
+      }
+      self.mutated();
+    }
+  }
+
+  // Same rules as insert_before.
+  pub fn insert_after(&mut self, node: NonNull<Node<T>>)
+  {
+    debug_assert!(
+      unsafe { !node.as_ref().is_linked() },
+      "inserted a node that is still linked into a list"
+    );
+    let link_current = self.current;
+    unsafe {
+      if let Some(p_current) = link_current
+      {
+        let current = &mut (*p_current.as_ptr());
+        if let Some(p_next) = current.next
+        {
+          let next = &mut (*p_next.as_ptr());
+          let new = &mut (*node.as_ptr());
+          next.prev = Some(node);
+          current.next = Some(node);
+
+          new.prev = Some(p_current);
+          new.next = Some(p_next);
+          self.list.len += 1;
+        }
+        // at back
+        else
+        {
+          self.list.push_back(node);
+        }
+      }
+      // and after the ghost is the front
+      else
+      {
+        self.list.push_front(node);
+      }
+      self.mutated();
+    }
+  }
+
+  // Unlinks nodes starting at the current one until pred matches (or the end is hit) and returns them.
+  // The cursor is left on the node that stopped it, which now sits at the old index.
+  pub fn take_until<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> List<T>
+  {
+    let Some(first) = self.current
+    else
+    {
+      return List::new();
+    };
+
+    unsafe {
+      let mut last = None;
+      let mut stop = Some(first);
+      let mut count = 0;
+      while let Some(node) = stop
+      {
+        if pred(&(*node.as_ptr()).elem)
+        {
+          break;
+        }
+        last = stop;
+        stop = (*node.as_ptr()).next;
+        count += 1;
+      }
+
+      self.current = stop;
+      if stop.is_none()
+      {
+        self.idx = None;
+      }
+
+      let Some(last) = last
+      else
+      {
+        return List::new();
+      };
+      let taken = self.list.unlink_range(first, last, count);
+      self.mutated();
+      taken
+    }
+  }
+
+  // Steps forward once, but only if the next element matches pred. Returns whether the cursor moved.
+  // Never moves onto the ghost, so a caller driven loop stops on the last matching node.
+  pub fn move_next_if<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> bool
+  {
+    let next = match self.current
+    {
+      Some(node) =>
+      unsafe { (*node.as_ptr()).next },
+      None => self.list.front,
+    };
+    match next
+    {
+      Some(node) if pred(unsafe { &(*node.as_ptr()).elem }) =>
+      {
+        self.move_next();
+        true
+      }
+      _ => false,
+    }
+  }
+
+  // Follows n links forward (or backward) from the current node, starting at the front (or back)
+  // when on the ghost.
+  fn walk_from_current(&self, n: usize, forward: bool) -> Link<T>
+  {
+    let mut node = match (self.current, forward)
+    {
+      (Some(current), _) => Some(current),
+      (None, true) => self.list.front,
+      (None, false) => self.list.back,
+    };
+    for _ in 0..n
+    {
+      node = node.and_then(|x| unsafe {
+        if forward
+        {
+          (*x.as_ptr()).next
+        }
+        else
+        {
+          (*x.as_ptr()).prev
+        }
+      });
+    }
+    node
+  }
+
+  // Looks n nodes past the current one without moving, peek_nth(0) is the current value.
+  // On the ghost the count starts at the front.
+  pub fn peek_nth(&mut self, n: usize) -> Option<&mut T>
+  {
+    self
+      .walk_from_current(n, true)
+      .map(|x| unsafe { &mut (*x.as_ptr()).elem })
+  }
+
+  // Looks n nodes before the current one without moving. On the ghost the count starts at the back.
+  pub fn peek_nth_back(&mut self, n: usize) -> Option<&mut T>
+  {
+    self
+      .walk_from_current(n, false)
+      .map(|x| unsafe { &mut (*x.as_ptr()).elem })
+  }
+
+  // Boxes a clone of the current element and inserts it right after, the cursor stays on the original.
+  // Does nothing on the ghost. The new node is a plain Box, free it with Box::from_raw once unlinked.
+  #[cfg(feature = "alloc")]
+  pub fn duplicate_current(&mut self)
+  where
+    T: Clone,
+  {
+    let Some(current) = self.current
+    else
+    {
+      return;
+    };
+    let elem = unsafe { (*current.as_ptr()).elem.clone() };
+    let node = NonNull::from(Box::leak(Box::new(Node::new(elem))));
+    self.insert_after(node);
+  }
+
+  // Applies f to the current element and everything after it, the cursor itself doesn't move.
+  pub fn map_in_place<F: FnMut(&mut T)>(&mut self, mut f: F)
+  {
+    let mut node = self.current;
+    while let Some(x) = node
+    {
+      unsafe {
+        f(&mut (*x.as_ptr()).elem);
+        node = (*x.as_ptr()).next;
+      }
+    }
+  }
+
+  // Yields up to n elements starting with the current one, without moving the cursor.
+  // Nothing is yielded on the ghost.
+  pub fn peek_next_n(&self, n: usize) -> impl Iterator<Item = &T>
+  {
+    core::iter::successors(self.current, |x| unsafe { (*x.as_ptr()).next })
+      .take(n)
+      .map(|x| unsafe { &(*x.as_ptr()).elem })
+  }
+
+  // Unlinks everything from the current node to the back and returns it, leaving the cursor on the ghost.
+  pub fn remove_rest(&mut self) -> List<T>
+  {
+    let Some(first) = self.current
+    else
+    {
+      return List::new();
+    };
+    let count = match self.idx
+    {
+      Some(idx) => self.list.len - idx,
+      // Index isn't known, so count the tail the slow way
+      None => core::iter::successors(Some(first), |x| unsafe { (*x.as_ptr()).next }).count(),
+    };
+    let rest = unsafe {
+      self
+        .list
+        .unlink_range(first, self.list.back.unwrap(), count)
+    };
+    self.current = None;
+    self.idx = None;
+    self.mutated();
+    rest
+  }
+
+  // Swaps the current node with node (which has to be in the same list) by relinking.
+  // The cursor stays on its node, so it follows its element to wherever node used to be.
+  pub fn swap_current_with(&mut self, node: NonNull<Node<T>>)
+  {
+    let Some(current) = self.current
+    else
+    {
+      return;
+    };
+    if current == node
+    {
+      return;
+    }
+    // The cursor is about to land where node currently sits
+    let new_idx = self.idx.and_then(|_| self.list.node_index(node));
+    self.list.swap_nodes(current, node);
+    self.idx = new_idx;
+    self.mutated();
+  }
+
+  // Unlinks the current node and everything after it one at a time, handing each to sink as it goes.
+  // Ends with the cursor on the ghost.
+  pub fn drain_forward(&mut self, mut sink: impl FnMut(NonNull<Node<T>>))
+  {
+    while self.current.is_some()
+    {
+      if let Some(node) = self.remove()
+      {
+        sink(node);
+      }
+    }
+  }
+
+  // How many elements come strictly after the current one.
+  // The ghost sits before the front as far as move_next is concerned, so on the ghost this is len.
+  pub fn remaining(&self) -> usize
+  {
+    match (self.current, self.idx)
+    {
+      (None, _) => self.list.len,
+      (Some(_), Some(idx)) => self.list.len.saturating_sub(idx + 1),
+      // Index isn't known, count the rest by hand
+      (Some(current), None) =>
+      {
+        core::iter::successors(unsafe { (*current.as_ptr()).next }, |x| unsafe {
+          (*x.as_ptr()).next
+        })
+        .count()
+      }
+    }
+  }
+
+  // Removes and returns the current node if pred matches its element, otherwise just moves to the next.
+  // Either way the cursor ends up on what followed the old current node. Does nothing on the ghost.
+  pub fn remove_if<F: FnMut(&mut T) -> bool>(&mut self, mut pred: F) -> Link<T>
+  {
+    let current = self.current?;
+    if pred(unsafe { &mut (*current.as_ptr()).elem })
+    {
+      self.remove()
+    }
+    else
+    {
+      self.move_next();
+      None
+    }
+  }
+
+  // insert_before that hands the node back for chaining. From here on the list owns node's position,
+  // it must not be freed until it has been unlinked again.
+  pub fn splice_node_before(&mut self, node: NonNull<Node<T>>) -> NonNull<Node<T>>
+  {
+    self.insert_before(node);
+    node
+  }
+
+  // insert_after that hands the node back for chaining, same ownership rules as splice_node_before.
+  pub fn splice_node_after(&mut self, node: NonNull<Node<T>>) -> NonNull<Node<T>>
+  {
+    self.insert_after(node);
+    node
+  }
+
+  // Mutable references to the previous, current and next elements all at once.
+  // None on the ghost, and the neighbours are None past either end.
+  pub fn neighbors_mut(&mut self) -> Option<(Option<&mut T>, &mut T, Option<&mut T>)>
+  {
+    let current = self.current?;
+    // All three are different nodes, so none of the references alias
+    unsafe {
+      let node = &mut (*current.as_ptr());
+      let prev = node.prev.map(|x| &mut (*x.as_ptr()).elem);
+      let next = node.next.map(|x| &mut (*x.as_ptr()).elem);
+      Some((prev, &mut node.elem, next))
+    }
+  }
+
+  // Threads state through f from the current element to the back, f can change the elements as it goes.
+  // The cursor doesn't move, the final state is returned.
+  pub fn scan_mut<St, F: FnMut(&mut St, &mut T)>(&mut self, init: St, mut f: F) -> St
+  {
+    let mut state = init;
+    self.map_in_place(|x| f(&mut state, x));
+    state
+  }
+
+  pub fn current_index(&self) -> Option<usize>
+  {
+    self.idx
+  }
+
+  // Splices all of nodes (in slice order) in right before the current node with a single seam relink.
+  // Same rules as insert_before for each node, and likewise lands at the back on the ghost.
+  pub fn insert_slice_before(&mut self, nodes: &[NonNull<Node<T>>])
+  {
+    let (Some(&first), Some(&last)) = (nodes.first(), nodes.last())
+    else
+    {
+      return;
+    };
+    unsafe {
+      for node in nodes
+      {
+        debug_assert!(
+          !node.as_ref().is_linked(),
+          "inserted a node that is still linked into a list"
+        );
+      }
+
+      // Chain the new nodes up among themselves first
+      for pair in nodes.windows(2)
+      {
+        (*pair[0].as_ptr()).next = Some(pair[1]);
+        (*pair[1].as_ptr()).prev = Some(pair[0]);
+      }
+
+      // then drop the whole chain into the seam before current, the ghost's seam being after the back
+      let prev = match self.current
+      {
+        Some(current) => (*current.as_ptr()).prev,
+        None => self.list.back,
+      };
+      (*first.as_ptr()).prev = prev;
+      (*last.as_ptr()).next = self.current;
+      self.list.set_prev_of(self.current, Some(last));
+      self.list.set_next_of(prev, Some(first));
+    }
+    self.list.len += nodes.len();
+    self.idx = self.idx.map(|x| x + nodes.len());
+    self.mutated();
+  }
+
+  // Moves delta steps, forward when positive and backward when negative.
+  // Stops on the ghost if it runs off either end rather than wrapping around.
+  pub fn step(&mut self, delta: isize)
+  {
+    for _ in 0..delta.unsigned_abs()
+    {
+      if delta > 0
+      {
+        self.move_next();
+      }
+      else
+      {
+        self.move_prev();
+      }
+      if self.current.is_none()
+      {
+        break;
+      }
+    }
+  }
+
+  // Jumps straight onto the back node in O(1), or the ghost if the list is empty.
+  pub fn fast_forward(&mut self)
+  {
+    self.check_generation();
+    self.current = self.list.back;
+    self.idx = self.list.len_minus_one();
+  }
+
+  // Shared references to the previous, current and next elements. On the ghost all three are None.
+  pub fn neighbors(&self) -> (Option<&T>, Option<&T>, Option<&T>)
+  {
+    let Some(current) = self.current
+    else
+    {
+      return (None, None, None);
+    };
+    unsafe {
+      let node = &(*current.as_ptr());
+      (
+        node.prev.map(|x| &(*x.as_ptr()).elem),
+        Some(&node.elem),
+        node.next.map(|x| &(*x.as_ptr()).elem),
+      )
+    }
+  }
+
+  // Puts the cursor on the front node (not the ghost before it), or the ghost if the list is empty.
+  pub fn move_to_front(&mut self)
+  {
+    self.check_generation();
+    self.current = self.list.front;
+    self.idx = self.list.front.map(|_| 0);
+  }
+
+  // Swaps up to n nodes starting at the current one for all of replacement's nodes, returning the
+  // removed ones and leaving replacement empty. The cursor lands on the first inserted node, or on
+  // whatever followed the removed nodes if replacement was empty. On the ghost nothing is removed and
+  // replacement goes onto the back.
+  pub fn replace_next_n(&mut self, n: usize, replacement: &mut List<T>) -> List<T>
+  {
+    let mut removed = List::new();
+    let mut after = self.current;
+    if let (Some(first), true) = (self.current, n > 0)
+    {
+      let mut last = first;
+      let mut count = 1;
+      while let (Some(next), true) = (unsafe { (*last.as_ptr()).next }, count < n)
+      {
+        last = next;
+        count += 1;
+      }
+      after = unsafe { (*last.as_ptr()).next };
+      removed = unsafe { self.list.unlink_range(first, last, count) };
+    }
+
+    // A cursor on the ghost ends up on the first node appended after the old back
+    let idx = match self.current
+    {
+      Some(_) => self.idx,
+      None => Some(self.list.len),
+    };
+    self.current = replacement.front.or(after);
+    unsafe { self.list.splice_before(after, replacement) };
+    self.idx = idx.filter(|_| self.current.is_some());
+    self.mutated();
+    removed
+  }
+
+  // Calls f on the current element and every one after it, moving along as it goes and finishing on
+  // the ghost. The read-only counterpart to drain_forward.
+  pub fn visit_forward<F: FnMut(&T)>(&mut self, mut f: F)
+  {
+    while let Some(x) = self.current
+    {
+      f(unsafe { &(*x.as_ptr()).elem });
+      self.move_next();
+    }
+  }
+
+  // Cuts off everything after the current node and returns it, the cursor stays where it is.
+  // That leaves it on the new back, so the next move_next lands on the ghost.
+  // On the ghost the whole list is moved out.
+  pub fn split_after(&mut self) -> List<T>
+  {
+    let Some(current) = self.current
+    else
+    {
+      let mut all = List::new();
+      List::swap_lists(&mut all, self.list);
+      self.mutated();
+      return all;
+    };
+    let Some(next) = (unsafe { (*current.as_ptr()).next })
+    else
+    {
+      return List::new();
+    };
+    let count = match self.idx
+    {
+      Some(idx) => self.list.len - idx - 1,
+      None => self.remaining(),
+    };
+    let tail = unsafe { self.list.unlink_range(next, self.list.back.unwrap(), count) };
+    self.mutated();
+    tail
+  }
+
+  // Splits the list into everything up to and including the current node, and everything after it.
+  // Both halves are moved out, so the list the cursor came from is left empty. On the ghost the first
+  // half is the whole list.
+  pub fn bisect(mut self) -> (List<T>, List<T>)
+  {
+    let back = match self.current
+    {
+      Some(_) => self.split_after(),
+      None => List::new(),
+    };
+    let mut front = List::new();
+    List::swap_lists(&mut front, self.list);
+    (front, back)
+  }
+
+  // Jumps onto node, which has to be in this list. The index isn't known afterwards (current_index is
+  // None) until the cursor passes through the ghost again.
+  pub fn set_current(&mut self, node: NonNull<Node<T>>)
+  {
+    self.check_generation();
+    self.current = Some(node);
+    self.idx = None;
+  }
+
+  // Jumps onto node and takes idx as its index on trust, for when the caller already knows it.
+  // A wrong idx isn't caught, it just silently throws off everything that relies on the index.
+  pub fn set_current_with_index(&mut self, node: NonNull<Node<T>>, idx: usize)
+  {
+    self.check_generation();
+    self.current = Some(node);
+    self.idx = Some(idx);
+  }
+
+  // Unlinks and returns the current node, leaving the cursor on the node that came before it (index
+  // one lower), or on the ghost if it was the front. Does nothing on the ghost.
+  pub fn remove_stay(&mut self) -> Link<T>
+  {
+    let current = self.current?;
+    unsafe {
+      let prev = (*current.as_ptr()).prev;
+      self.list.unlink_range(current, current, 1);
+      self.current = prev;
+    }
+    self.idx = match self.current
+    {
+      Some(_) => self.idx.and_then(|x| x.checked_sub(1)),
+      None => None,
+    };
+    self.mutated();
+    Some(current)
+  }
+
+  // The list's front element, readable without giving up the cursor.
+  pub fn front(&self) -> Option<&T>
+  {
+    self.list.front_val()
+  }
+
+  // The list's back element, readable without giving up the cursor.
+  pub fn back(&self) -> Option<&T>
+  {
+    self.list.back_val()
+  }
+
+  // move_next followed by current_value, so a whole pass is just `while let Some(x) = cursor.next_value()`.
+  pub fn next_value(&mut self) -> Option<&mut T>
+  {
+    self.move_next();
+    self.current_value()
+  }
+
+  // Calls f with the current element and its previous and next neighbours, all mutable, for rewriting
+  // a small window in place. Does nothing on the ghost.
+  pub fn with_next_n_mut<F: FnMut(&mut T, Option<&mut T>, Option<&mut T>)>(&mut self, mut f: F)
+  {
+    if let Some((prev, current, next)) = self.neighbors_mut()
+    {
+      f(current, prev, next);
+    }
+  }
+
+  // insert_before, but only if no element already in the list is equal to node's per eq. Returns
+  // whether it went in, a rejected node is left untouched for the caller.
+  pub fn insert_before_if_absent<F: FnMut(&T, &T) -> bool>(
+    &mut self,
+    node: NonNull<Node<T>>,
+    mut eq: F,
+  ) -> bool
+  {
+    let elem = unsafe { &(*node.as_ptr()).elem };
+    if self.list.iter().any(|x| eq(x, elem))
+    {
+      return false;
+    }
+    self.insert_before(node);
+    true
+  }
+
+  /// Removes the current node like remove, frees it and hands back the element by value.
+  ///
+  /// # Safety
+  /// The current node must have been allocated as a `Box<Node<T>>`, e.g. via Box::leak.
+  #[cfg(feature = "alloc")]
+  pub unsafe fn take_current(&mut self) -> Option<T>
+  {
+    self
+      .remove()
+      .map(|x| unsafe { Box::from_raw(x.as_ptr()).elem })
+  }
+
+  // next_value for read-only passes, move_next then a shared borrow of the new current.
+  pub fn advance(&mut self) -> Option<&T>
+  {
+    self.move_next();
+    self.current.map(|x| unsafe { &(*x.as_ptr()).elem })
+  }
+
+  // Moves other's nodes in before the current node back to front, so other's back ends up furthest
+  // from current, leaving other empty. Reverses other in place first then splices once, O(m). On
+  // the ghost they go onto the list's back. The cursor stays on its node.
+  pub fn splice_before_reversed(&mut self, other: &mut List<T>)
+  {
+    let (Some(first), Some(last)) = (other.front, other.back)
+    else
+    {
+      return;
+    };
+    let count = other.len;
+    unsafe {
+      other.reverse_chain(first, last);
+      self.list.splice_before(self.current, other);
+    }
+    self.idx = self.idx.map(|x| x + count);
+    self.mutated();
+  }
+
+  // Steps back for as long as same(previous, current) holds, ending on the first node of the run the
+  // cursor is in. Doesn't move on the ghost or when the run starts here.
+  pub fn rewind_to_run_start<F: FnMut(&T, &T) -> bool>(&mut self, mut same: F)
+  {
+    while let Some(current) = self.current
+    {
+      unsafe {
+        match (*current.as_ptr()).prev
+        {
+          Some(prev) if same(&(*prev.as_ptr()).elem, &(*current.as_ptr()).elem) => self.move_prev(),
+          _ => return,
+        }
+      }
+    }
+  }
+
+  // Length of the run starting at the current node, counting it and each following node for which
+  // same(previous, node) holds. The cursor doesn't move, 0 on the ghost.
+  pub fn count_run<F: FnMut(&T, &T) -> bool>(&mut self, mut same: F) -> usize
+  {
+    let Some(mut node) = self.current
+    else
+    {
+      return 0;
+    };
+    let mut count = 1;
+    unsafe {
+      while let Some(next) = (*node.as_ptr()).next
+      {
+        if !same(&(*node.as_ptr()).elem, &(*next.as_ptr()).elem)
+        {
+          break;
+        }
+        node = next;
+        count += 1;
+      }
+    }
+    count
+  }
+
+  // Swaps the len_a nodes starting at current with the len_b nodes right after them, by relinking the
+  // three seams. The cursor stays on its node, which moves len_b places back. Does nothing on the
+  // ghost or when either block is empty, panics if the list runs out before both blocks are filled.
+  pub fn swap_runs(&mut self, len_a: usize, len_b: usize)
+  {
+    let Some(a_first) = self.current
+    else
+    {
+      return;
+    };
+    if len_a == 0 || len_b == 0
+    {
+      return;
+    }
+    let nth_after = |mut node: NonNull<Node<T>>, n: usize| {
+      for _ in 0..n
+      {
+        node =
+          unsafe { (*node.as_ptr()).next }.expect("swap_runs blocks run past the back of the list");
+      }
+      node
+    };
+    let a_last = nth_after(a_first, len_a - 1);
+    let b_first = nth_after(a_last, 1);
+    let b_last = nth_after(b_first, len_b - 1);
+    unsafe {
+      let before = (*a_first.as_ptr()).prev;
+      let after = (*b_last.as_ptr()).next;
+
+      // before, B, A, after
+      self.list.set_next_of(before, Some(b_first));
+      (*b_first.as_ptr()).prev = before;
+      (*b_last.as_ptr()).next = Some(a_first);
+      (*a_first.as_ptr()).prev = Some(b_last);
+      (*a_last.as_ptr()).next = after;
+      self.list.set_prev_of(after, Some(a_last));
+    }
+    self.idx = self.idx.map(|x| x + len_b);
+    self.mutated();
+  }
+
+  // Read-only peek_nth/peek_nth_back in one, offset places ahead when positive and behind when
+  // negative. Counting from the ghost works the same way as for those two.
+  pub fn value_at_offset(&self, offset: isize) -> Option<&T>
+  {
+    self
+      .walk_from_current(offset.unsigned_abs(), offset >= 0)
+      .map(|x| unsafe { &(*x.as_ptr()).elem })
+  }
+
+  // Moves forward onto the nth element (counting from 0) matching pred, the current one included, or
+  // from the front on the ghost. Returns whether it got there, a miss leaves the cursor on the ghost.
+  pub fn move_to_nth_match<F: FnMut(&T) -> bool>(&mut self, mut n: usize, mut pred: F) -> bool
+  {
+    if self.current.is_none()
+    {
+      self.move_next();
+    }
+    while let Some(x) = self.current
+    {
+      if pred(unsafe { &(*x.as_ptr()).elem })
+      {
+        if n == 0
+        {
+          return true;
+        }
+        n -= 1;
+      }
+      self.move_next();
+    }
+    false
+  }
+
+  /// Folds the next node's element into the current one with merge, then unlinks and frees that
+  /// node. Returns whether there was a next node to merge, always false on the ghost.
+  ///
+  /// # Safety
+  /// The next node must have been allocated as a `Box<Node<T>>`, e.g. via Box::leak.
+  #[cfg(feature = "alloc")]
+  pub unsafe fn coalesce_next<F: FnMut(&mut T, T)>(&mut self, mut merge: F) -> bool
+  {
+    let Some(current) = self.current
+    else
+    {
+      return false;
+    };
+    let Some(next) = (unsafe { (*current.as_ptr()).next })
+    else
+    {
+      return false;
+    };
+    unsafe {
+      self.list.unlink_range(next, next, 1);
+      self.mutated();
+      merge(
+        &mut (*current.as_ptr()).elem,
+        Box::from_raw(next.as_ptr()).elem,
+      );
+    }
+    true
+  }
+
+  // insert_slice_before for a lazy source, each node goes in before the current one as the iterator
+  // hands it over, so they end up in iteration order.
+  pub fn insert_before_from_iter<I: IntoIterator<Item = NonNull<Node<T>>>>(&mut self, nodes: I)
+  {
+    for node in nodes
+    {
+      self.insert_before(node);
+    }
+  }
+
+  // The first node from the current one onwards (the current one included, the front on the ghost)
+  // whose element matches pred. The cursor stays put.
+  pub fn find_next_node<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Link<T>
+  {
+    let mut node = self.current.or(self.list.front);
+    while let Some(x) = node
+    {
+      unsafe {
+        if pred(&(*x.as_ptr()).elem)
+        {
+          return Some(x);
+        }
+        node = (*x.as_ptr()).next;
+      }
+    }
+    None
+  }
+
+  // extract_if for the current node and everything after it: matching nodes are unlinked into the
+  // returned list in order, the rest stay put. Ends with the cursor on the ghost like drain_forward.
+  pub fn remove_matching_forward<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> List<T>
+  {
+    let mut removed = List::new();
+    while self.current.is_some()
+    {
+      if let Some(node) = self.remove_if(|x| pred(x))
+      {
+        removed.push_back(node);
+      }
+    }
+    removed
+  }
+}
+
+#[cfg(test)]
+mod test
+{
+  use super::{List, Node, RotateDir};
+  use core::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::NonNull,
+  };
+  use std::alloc::System;
+
+  type NodeType = Node<usize>;
+  const NODE_LAYOUT: Layout =
+    unsafe { Layout::from_size_align_unchecked(size_of::<NodeType>(), align_of::<NodeType>()) };
+
+  fn alloc_node<T>(elem: T) -> NonNull<Node<T>>
+  {
+    unsafe {
+      let ret = NonNull::new(System.alloc(Layout::new::<Node<T>>()) as *mut Node<T>).unwrap();
+      ret.write(Node::new(elem));
+      ret
+    }
+  }
+
+  fn free_nodes<T>(nodes: Vec<NonNull<Node<T>>>)
+  {
+    for node in nodes
+    {
+      unsafe { System.dealloc(node.as_ptr() as *mut u8, Layout::new::<Node<T>>()) };
+    }
+  }
+
+  // Builds a list front to back out of elems, also returning the nodes so they can be freed later
+  fn list_from<T: Copy>(elems: &[T]) -> (List<T>, Vec<NonNull<Node<T>>>)
+  {
+    let mut list = List::new();
+    let mut nodes = Vec::with_capacity(elems.len());
+    for &elem in elems
+    {
+      let node = alloc_node(elem);
+      list.push_back(node);
+      nodes.push(node);
+    }
+    (list, nodes)
+  }
+
+  fn values<T: Copy>(list: &List<T>) -> Vec<T>
+  {
+    list.check_invariants();
+    let mut ret = Vec::with_capacity(list.len());
+    let mut current = list.front;
+    while let Some(node) = current
+    {
+      unsafe {
+        ret.push((*node.as_ptr()).elem);
+        current = (*node.as_ptr()).next;
+      }
+    }
+    ret
+  }
+
+  // Frees every node of a list whose nodes came from Box
+  #[cfg(feature = "alloc")]
+  fn free_boxed<T>(mut list: List<T>)
+  {
+    while let Some(node) = list.pop_front()
+    {
+      unsafe { drop(Box::from_raw(node.as_ptr())) };
+    }
+  }
+
+  // list_from for tests that panic partway: the nodes live in storage on the caller's stack, so there
+  // is nothing to free (or leak, as far as Miri is concerned) once the test unwinds
+  fn list_on_stack<T, const N: usize>(
+    storage: &mut [Node<T>; N],
+  ) -> (List<T>, [NonNull<Node<T>>; N])
+  {
+    let nodes = storage.each_mut().map(NonNull::from);
+    (nodes.into_iter().collect(), nodes)
+  }
+
+  #[test]
+  fn test_basic_front()
+  {
+    let mut list = List::new();
+
+    // Try to break an empty list
+    assert_eq!(list.len(), 0);
+    assert_eq!(list.pop_front(), None);
+    assert_eq!(list.len(), 0);
+
+    unsafe {
+      let ten = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+      let twe = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+      let thr = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+      let frt = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+      ten.write(Node::new(10));
+      twe.write(Node::new(20));
+      thr.write(Node::new(30));
+      frt.write(Node::new(40));
+      // Try to break a one item list
+      list.push_front(ten);
+      assert_eq!(list.len(), 1);
+      assert_eq!(list.pop_front(), Some(ten));
+      assert_eq!(list.len(), 0);
+      assert_eq!(list.pop_front(), None);
+      assert_eq!(list.len(), 0);
+
+      // Mess around
+      list.push_front(ten);
+      assert_eq!(list.len(), 1);
+      list.push_front(twe);
+      assert_eq!(list.len(), 2);
+      list.push_front(thr);
+      assert_eq!(list.len(), 3);
+      assert_eq!(list.pop_front(), Some(thr));
+      assert_eq!(list.len(), 2);
+      list.push_front(frt);
+      assert_eq!(list.len(), 3);
+      assert_eq!(list.pop_front(), Some(frt));
+      assert_eq!(list.len(), 2);
+      assert_eq!(list.pop_front(), Some(twe));
+      assert_eq!(list.len(), 1);
+      assert_eq!(list.pop_front(), Some(ten));
+      assert_eq!(list.len(), 0);
+      assert_eq!(list.pop_front(), None);
+      assert_eq!(list.len(), 0);
+      assert_eq!(list.pop_front(), None);
+      assert_eq!(list.len(), 0);
+
+      System.dealloc(ten.as_ptr() as *mut u8, NODE_LAYOUT);
+      System.dealloc(twe.as_ptr() as *mut u8, NODE_LAYOUT);
+      System.dealloc(thr.as_ptr() as *mut u8, NODE_LAYOUT);
+      System.dealloc(frt.as_ptr() as *mut u8, NODE_LAYOUT);
+    }
+  }
+
+  #[test]
+  fn test_check_invariants()
+  {
+    let mut list = List::new();
+    list.check_invariants();
+    unsafe {
+      let one = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+      let two = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+      one.write(Node::new(1));
+      two.write(Node::new(2));
+      list.push_back(one);
+      list.check_invariants();
+      list.push_back(two);
+      list.check_invariants();
+      list.pop_front();
+      list.check_invariants();
+      list.pop_front();
+      list.check_invariants();
+
+      System.dealloc(one.as_ptr() as *mut u8, NODE_LAYOUT);
+      System.dealloc(two.as_ptr() as *mut u8, NODE_LAYOUT);
+    }
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic]
+  fn test_check_invariants_cycle()
+  {
+    let mut storage = [1, 2, 3].map(Node::new);
+    let (list, nodes) = list_on_stack(&mut storage);
+    unsafe { (*nodes[2].as_ptr()).next = Some(nodes[0]) }; // back to the front, forming the cycle
+    list.check_invariants();
+  }
+
+  #[test]
+  fn test_cursor_remove()
+  {
+    const COUNT: usize = 100;
+    let mut alloc_vec = Vec::with_capacity(COUNT);
+    let mut list = List::new();
+    for i in 0..COUNT
+    {
+      let ptr = unsafe {
+        let ret = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+        ret.as_ptr().write(Node::new(i));
+        ret
+      };
+      list.push_front(ptr);
+      alloc_vec.push(ptr);
+    }
+
+    let mut counter = 0;
+    let start_idx = rand::random_range(0..list.len());
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    for _ in 0..start_idx
+    {
+      cursor.move_next();
+    }
+
+    while cursor.current_value().is_some()
+    {
+      cursor.remove();
+      counter += 1;
+    }
+
+    while list.pop_front().is_some()
+    {
+      counter += 1;
+    }
+    assert!(counter == COUNT);
+
+    while let Some(x) = alloc_vec.pop()
+    {
+      unsafe { System.dealloc(x.as_ptr() as *mut u8, NODE_LAYOUT) };
+    }
+  }
+
+  #[test]
+  fn test_cursor_insert_before()
+  {
+    const COUNT: usize = 100;
+    const INSERT_MAX: usize = 20;
+    let mut alloc_vec = Vec::with_capacity(COUNT);
+    let mut list = List::new();
+    for i in 0..COUNT
+    {
+      let ptr = unsafe {
+        let ret = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+        ret.as_ptr().write(Node::new(i));
+        ret
+      };
+      list.push_front(ptr);
+      alloc_vec.push(ptr);
+    }
+
+    let start_idx = rand::random_range(0..list.len());
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    for _ in 0..start_idx
+    {
+      cursor.move_next();
+    }
+    let insert_count = rand::random_range(1..INSERT_MAX);
+    for i in 0..insert_count
+    {
+      let ptr = unsafe {
+        let ret = NonNull::new(System.alloc(NODE_LAYOUT) as *mut NodeType).unwrap();
+        ret.as_ptr().write(Node::new(i));
+        ret
+      };
+      cursor.insert_before(ptr);
+      alloc_vec.push(ptr);
+    }
+    dbg!(list.len());
+    dbg!(insert_count);
+    assert!(list.len() == COUNT + insert_count);
+
+    while let Some(x) = alloc_vec.pop()
+    {
+      unsafe { System.dealloc(x.as_ptr() as *mut u8, NODE_LAYOUT) };
+    }
+  }
+
+  #[test]
+  fn test_cursor_take_until()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 5, 7]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    let taken = cursor.take_until(|&x| x == 5);
+    assert_eq!(cursor.current_value(), Some(&mut 5));
+    assert_eq!(cursor.idx, Some(0));
+    assert_eq!(values(&taken), [1, 2, 3]);
+    assert_eq!(values(&list), [5, 7]);
+
+    // Nothing matches, so everything from the cursor onward is taken
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.move_next();
+    let taken = cursor.take_until(|&x| x == 100);
+    assert_eq!(cursor.current_value(), None);
+    assert_eq!(values(&taken), [7]);
+    assert_eq!(values(&list), [5]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_eq_by()
+  {
+    let close = |a: &f64, b: &f64| (a - b).abs() < 0.001;
+    let (a, a_nodes) = list_from(&[0.1 + 0.2, 1.0, 2.5]);
+    let (b, b_nodes) = list_from(&[0.3, 1.0, 2.5]);
+    let (c, c_nodes) = list_from(&[0.3, 1.0, 2.6]);
+    let (d, d_nodes) = list_from(&[0.3, 1.0]);
+    assert!(a.eq_by(&b, close));
+    assert!(!a.eq_by(&c, close));
+    assert!(!a.eq_by(&d, close));
+    assert!(List::<f64>::new().eq_by(&List::new(), close));
+
+    free_nodes(a_nodes);
+    free_nodes(b_nodes);
+    free_nodes(c_nodes);
+    free_nodes(d_nodes);
+  }
+
+  #[test]
+  fn test_shift_values()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    list.shift_values_left(2);
+    assert_eq!(values(&list), [2, 3, 4, 0, 1]);
+
+    // The nodes are still in the same order, only their values moved
+    let mut current = list.front;
+    for &node in &nodes
+    {
+      assert_eq!(current, Some(node));
+      current = unsafe { (*node.as_ptr()).next };
+    }
+
+    list.shift_values_right(2);
+    assert_eq!(values(&list), [0, 1, 2, 3, 4]);
+    list.shift_values_left(5);
+    assert_eq!(values(&list), [0, 1, 2, 3, 4]);
+    list.shift_values_right(7);
+    assert_eq!(values(&list), [3, 4, 0, 1, 2]);
+
+    let mut empty = List::<usize>::new();
+    empty.shift_values_left(3);
+    empty.shift_values_right(3);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "behind its back")]
+  fn test_cursor_stale_generation()
+  {
+    let mut storage = [1, 2, 3].map(Node::new);
+    let mut extra = Node::new(4);
+    let (mut list, _) = list_on_stack(&mut storage);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.remove();
+    cursor.move_next(); // the cursor's own changes keep it in sync
+
+    // Stand in for a cursor method that forgets to call mutated by going straight to the private list
+    cursor.list.push_back(NonNull::from(&mut extra));
+    cursor.move_next();
+  }
+
+  #[test]
+  fn test_split_at_node()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4, 5]);
+    let tail = list.split_at_node(nodes[4]);
+    assert_eq!(values(&list), [0, 1, 2, 3]);
+    assert_eq!(values(&tail), [4, 5]);
+
+    let tail = list.split_at_node(nodes[1]);
+    assert_eq!(values(&list), [0]);
+    assert_eq!(values(&tail), [1, 2, 3]);
+
+    // Splitting at the front moves everything over
+    let tail = list.split_at_node(nodes[0]);
+    assert!(list.is_empty());
+    assert_eq!(values(&list), []);
+    assert_eq!(values(&tail), [0]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_node_where()
+  {
+    use rand::seq::SliceRandom;
+
+    let mut elems: Vec<usize> = (0..50).collect();
+    elems.shuffle(&mut rand::rng());
+    let (list, nodes) = list_from(&elems);
+
+    let first_even = *elems.iter().find(|&&x| x % 2 == 0).unwrap();
+    let last_even = *elems.iter().rfind(|&&x| x % 2 == 0).unwrap();
+    let first = list.first_node_where(|&x| x % 2 == 0).unwrap();
+    let last = list.last_node_where(|&x| x % 2 == 0).unwrap();
+    unsafe {
+      assert_eq!(*first.as_ref().elem(), first_even);
+      assert_eq!(*last.as_ref().elem(), last_even);
+    }
+    assert_eq!(list.first_node_where(|&x| x > 100), None);
+    assert_eq!(list.last_node_where(|&x| x > 100), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_move_next_if()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 4, 7, 9, 12]);
+    let mut cursor = list.cursor_mut();
+    let mut steps = 0;
+    while cursor.move_next_if(|&x| x < 8)
+    {
+      steps += 1;
+    }
+    assert_eq!(steps, 4);
+    assert_eq!(cursor.current_value(), Some(&mut 7));
+    assert_eq!(cursor.idx, Some(3));
+
+    // Stays on the back node instead of falling onto the ghost
+    while cursor.move_next_if(|_| true)
+    {}
+    assert_eq!(cursor.current_value(), Some(&mut 12));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  fn test_collect_refs()
+  {
+    let (list, nodes) = list_from(&[3, 1, 4, 1, 5]);
+    let refs = list.collect_refs();
+    assert_eq!(refs, [&3, &1, &4, &1, &5]);
+    for (r, node) in refs.iter().zip(&nodes)
+    {
+      assert!(core::ptr::eq(*r, unsafe { node.as_ref().elem() }));
+    }
+    assert!(List::<usize>::new().collect_refs().is_empty());
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_move_prev_single()
+  {
+    let (mut list, nodes) = list_from(&[7]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_prev();
+    assert_eq!(cursor.idx, Some(0));
+    assert_eq!(cursor.current_value(), Some(&mut 7));
+    cursor.move_prev();
+    assert_eq!(cursor.idx, None);
+    assert_eq!(cursor.current_value(), None);
+    // and around again
+    cursor.move_prev();
+    assert_eq!(cursor.idx, Some(0));
+
+    // An empty list never leaves the ghost
+    let mut empty = List::<usize>::new();
+    let mut cursor = empty.cursor_mut();
+    cursor.move_prev();
+    assert_eq!(cursor.idx, None);
+    cursor.move_next();
+    assert_eq!(cursor.idx, None);
+    assert_eq!(cursor.current_value(), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_extend()
+  {
+    let (mut list, mut nodes) = list_from(&[1, 2]);
+    let more: Vec<_> = (3..6).map(alloc_node).collect();
+    list.extend(more.iter().copied());
+    assert_eq!(list.len(), 5);
+    assert_eq!(values(&list), [1, 2, 3, 4, 5]);
+    list.extend(core::iter::empty());
+    assert_eq!(values(&list), [1, 2, 3, 4, 5]);
+    nodes.extend(more);
+
+    let more: Vec<_> = (6..8).map(alloc_node).collect();
+    let collected: List<usize> = more.iter().copied().collect();
+    assert_eq!(values(&collected), [6, 7]);
+    nodes.extend(more);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_peek_nth()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    assert_eq!(cursor.peek_nth(0), Some(&mut 0));
+    assert_eq!(cursor.peek_nth_back(0), Some(&mut 4));
+
+    cursor.move_next();
+    cursor.move_next();
+    assert_eq!(cursor.peek_nth(2), Some(&mut 3));
+    assert_eq!(cursor.peek_nth(3), Some(&mut 4));
+    assert_eq!(cursor.peek_nth(4), None);
+    assert_eq!(cursor.peek_nth(100), None);
+    assert_eq!(cursor.peek_nth_back(1), Some(&mut 0));
+    assert_eq!(cursor.peek_nth_back(2), None);
+
+    // Peeking never moves the cursor
+    assert_eq!(cursor.idx, Some(1));
+    assert_eq!(cursor.current_value(), Some(&mut 1));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  fn test_merge_k_sorted()
+  {
+    let (a, mut nodes) = list_from(&[(1, 'a'), (4, 'a')]);
+    let (b, b_nodes) = list_from(&[(2, 'b'), (4, 'b'), (5, 'b')]);
+    let (c, c_nodes) = list_from(&[(3, 'c'), (4, 'c'), (6, 'c')]);
+    nodes.extend(b_nodes);
+    nodes.extend(c_nodes);
+
+    let merged = List::merge_k_sorted(vec![a, List::new(), b, c], |x, y| x.0.cmp(&y.0));
+    assert_eq!(
+      values(&merged),
+      [
+        (1, 'a'),
+        (2, 'b'),
+        (3, 'c'),
+        (4, 'a'),
+        (4, 'b'),
+        (4, 'c'),
+        (5, 'b'),
+        (6, 'c')
+      ]
+    );
+    assert!(List::<usize>::merge_k_sorted(Vec::new(), |x, y| x.cmp(y)).is_empty());
+
+    // Enough lists to give the heap some depth, tagged with (list, position) to check stability
+    let mut lists = Vec::new();
+    let mut expected = Vec::new();
+    let mut tagged_nodes = Vec::new();
+    for list_idx in 0..20
+    {
+      let mut keys: Vec<usize> = (0..rand::random_range(0..10))
+        .map(|_| rand::random_range(0..8))
+        .collect();
+      keys.sort();
+      let elems: Vec<_> = keys
+        .iter()
+        .enumerate()
+        .map(|(pos, &k)| (k, list_idx, pos))
+        .collect();
+      expected.extend(elems.iter().copied());
+      let (list, list_nodes) = list_from(&elems);
+      lists.push(list);
+      tagged_nodes.extend(list_nodes);
+    }
+    expected.sort_by_key(|x| x.0);
+    let merged = List::merge_k_sorted(lists, |x, y| x.0.cmp(&y.0));
+    assert_eq!(values(&merged), expected);
+
+    free_nodes(nodes);
+    free_nodes(tagged_nodes);
+  }
+
+  #[test]
+  fn test_cursor_insert_after()
+  {
+    let (mut list, mut nodes) = list_from(&[1, 2]);
+    let extra = [alloc_node(10), alloc_node(20)];
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.insert_after(extra[0]);
+    assert_eq!(cursor.current_value(), Some(&mut 1));
+    cursor.move_next();
+    cursor.move_next();
+    cursor.insert_after(extra[1]);
+    assert_eq!(cursor.idx, Some(2));
+    assert_eq!(values(&list), [1, 10, 2, 20]);
+    nodes.extend(extra);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  fn test_cursor_duplicate_current()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3]);
+    let mut cursor = list.cursor_mut();
+    cursor.duplicate_current(); // ghost, nothing happens
+    cursor.move_next();
+    cursor.move_next();
+    cursor.duplicate_current();
+    assert_eq!(cursor.current_value(), Some(&mut 2));
+    assert_eq!(cursor.idx, Some(1));
+    assert_eq!(values(cursor.list), [1, 2, 2, 3]);
+    cursor.move_next();
+    let clone = cursor.remove().unwrap();
+    assert!(!nodes.contains(&clone));
+    assert_eq!(unsafe { *clone.as_ref().elem() }, 2);
+    assert_eq!(list.len(), 3);
+
+    unsafe { drop(Box::from_raw(clone.as_ptr())) };
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_bulk_remove_nodes()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    list.bulk_remove_nodes(&[nodes[5], nodes[2], nodes[3]]);
+    assert_eq!(values(&list), [0, 1, 4, 6, 7, 8, 9]);
+    unsafe {
+      assert_eq!(nodes[2].as_ref().next_node(), None);
+      assert_eq!(nodes[2].as_ref().prev_node(), None);
+    }
+
+    // front, back, and then the last one standing
+    list.bulk_remove_nodes(&[nodes[0], nodes[9]]);
+    assert_eq!(values(&list), [1, 4, 6, 7, 8]);
+    list.bulk_remove_nodes(&[nodes[1], nodes[4], nodes[6], nodes[7]]);
+    assert_eq!(values(&list), [8]);
+    list.bulk_remove_nodes(&[nodes[8]]);
+    assert!(list.is_empty());
+    assert_eq!(values(&list), []);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_map_in_place()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.map_in_place(|x: &mut i32| *x += 100); // ghost, nothing to map
+    cursor.move_next();
+    cursor.move_next();
+    cursor.move_next();
+    cursor.map_in_place(|x| *x = -*x);
+    assert_eq!(cursor.idx, Some(2));
+    assert_eq!(cursor.current_value(), Some(&mut -2));
+    assert_eq!(values(&list), [0, 1, -2, -3, -4]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_try_push()
+  {
+    const MAX: usize = 3;
+    let nodes: Vec<_> = (0..5).map(alloc_node).collect();
+    let mut list = List::new();
+    assert_eq!(list.try_push_back(nodes[0], MAX), Ok(()));
+    assert_eq!(list.try_push_front(nodes[1], MAX), Ok(()));
+    assert_eq!(list.try_push_back(nodes[2], MAX), Ok(()));
+    assert_eq!(list.try_push_back(nodes[3], MAX), Err(nodes[3]));
+    assert_eq!(list.try_push_front(nodes[4], MAX), Err(nodes[4]));
+    assert_eq!(list.len(), MAX);
+    assert_eq!(values(&list), [1, 0, 2]);
+
+    // Rejected nodes are untouched and can go somewhere else
+    let mut other = List::new();
+    assert_eq!(other.try_push_back(nodes[3], 0), Err(nodes[3]));
+    other.push_back(nodes[3]);
+    assert_eq!(values(&other), [3]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_elem_swap()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4]);
+    list.elem_swap(list.front.unwrap(), list.back.unwrap());
+    assert_eq!(values(&list), [4, 2, 3, 1]);
+    assert_eq!(list.front, Some(nodes[0]));
+    assert_eq!(list.back, Some(nodes[3]));
+    list.elem_swap(nodes[1], nodes[1]);
+    assert_eq!(values(&list), [4, 2, 3, 1]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_peek_next_n()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    assert_eq!(cursor.peek_next_n(3).count(), 0);
+    cursor.move_next();
+    cursor.move_next();
+    assert!(cursor.peek_next_n(3).eq(&[1, 2, 3]));
+    assert!(cursor.peek_next_n(10).eq(&[1, 2, 3, 4]));
+    assert_eq!(cursor.peek_next_n(0).count(), 0);
+    assert_eq!(cursor.current_value(), Some(&mut 1));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_with_single()
+  {
+    let (mut old, nodes) = list_from(&[1, 2, 3]);
+    let node = old.pop_back().unwrap();
+    unsafe { (*node.as_ptr()).prev = Some(nodes[0]) }; // pretend it's stale
+    let list = List::with_single(node);
+    assert_eq!(list.len(), 1);
+    assert_eq!(list.front_val(), Some(&3));
+    assert_eq!(list.back_val(), Some(&3));
+    unsafe {
+      assert_eq!(node.as_ref().next_node(), None);
+      assert_eq!(node.as_ref().prev_node(), None);
+    }
+    assert_eq!(values(&list), [3]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_remove_rest()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    assert!(cursor.remove_rest().is_empty());
+    cursor.move_next();
+    cursor.move_next();
+    cursor.move_next();
+    let rest = cursor.remove_rest();
+    assert_eq!(cursor.idx, None);
+    assert_eq!(cursor.current_value(), None);
+    assert_eq!(rest.len(), 3);
+    assert_eq!(values(&rest), [2, 3, 4]);
+    assert_eq!(list.len(), 2);
+    assert_eq!(values(&list), [0, 1]);
+
+    // From the front takes the lot
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    let rest = cursor.remove_rest();
+    assert_eq!(values(&rest), [0, 1]);
+    assert!(list.is_empty());
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_reverse_between()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    list.reverse_between(1, 3);
+    assert_eq!(values(&list), [0, 3, 2, 1, 4]);
+    list.reverse_between(0, 4);
+    assert_eq!(values(&list), [4, 1, 2, 3, 0]);
+    list.reverse_between(0, 1);
+    assert_eq!(values(&list), [1, 4, 2, 3, 0]);
+    list.reverse_between(3, 4);
+    assert_eq!(values(&list), [1, 4, 2, 0, 3]);
+    list.reverse_between(2, 2);
+    assert_eq!(values(&list), [1, 4, 2, 0, 3]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_reverse_between_out_of_range()
+  {
+    let mut storage = [0, 1, 2].map(Node::new);
+    let (mut list, _) = list_on_stack(&mut storage);
+    list.reverse_between(1, 3);
+  }
+
+  #[test]
+  fn test_detect_cycle()
+  {
+    let (list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    assert_eq!(list.detect_cycle(), None);
+    assert_eq!(List::<usize>::new().detect_cycle(), None);
+
+    // Point the back at node 1 so 1 -> 2 -> 3 -> 4 -> 1 loops
+    unsafe {
+      (*nodes[4].as_ptr()).next = Some(nodes[1]);
+      let looped = List::from_parts(Some(nodes[0]), Some(nodes[4]), 5);
+      assert_eq!(looped.detect_cycle(), Some(nodes[1]));
+      (*nodes[4].as_ptr()).next = Some(nodes[0]);
+      assert_eq!(looped.detect_cycle(), Some(nodes[0]));
+      (*nodes[4].as_ptr()).next = Some(nodes[4]);
+      assert_eq!(looped.detect_cycle(), Some(nodes[4]));
+    }
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_swap_nodes()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    list.swap_nodes(nodes[1], nodes[3]);
+    assert_eq!(values(&list), [0, 3, 2, 1, 4]);
+    list.swap_nodes(nodes[0], nodes[4]);
+    assert_eq!(values(&list), [4, 3, 2, 1, 0]);
+    // Adjacent in both argument orders, and at the ends
+    list.swap_nodes(nodes[3], nodes[2]);
+    assert_eq!(values(&list), [4, 2, 3, 1, 0]);
+    list.swap_nodes(nodes[1], nodes[3]);
+    assert_eq!(values(&list), [4, 2, 1, 3, 0]);
+    list.swap_nodes(nodes[4], nodes[2]);
+    assert_eq!(values(&list), [2, 4, 1, 3, 0]);
+    list.swap_nodes(nodes[0], nodes[3]);
+    assert_eq!(values(&list), [2, 4, 1, 0, 3]);
+
+    let (mut pair, pair_nodes) = list_from(&[0, 1]);
+    pair.swap_nodes(pair_nodes[1], pair_nodes[0]);
+    assert_eq!(values(&pair), [1, 0]);
+
+    free_nodes(nodes);
+    free_nodes(pair_nodes);
+  }
+
+  #[test]
+  fn test_cursor_swap_current_with()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.move_next();
+    cursor.move_next();
+    cursor.swap_current_with(nodes[4]);
+    assert_eq!(cursor.current_value(), Some(&mut 2));
+    assert_eq!(cursor.idx, Some(4));
+    // Now the neighbour right before it
+    cursor.swap_current_with(nodes[3]);
+    assert_eq!(cursor.current_value(), Some(&mut 2));
+    assert_eq!(cursor.idx, Some(3));
+    cursor.move_next();
+    assert_eq!(cursor.current_value(), Some(&mut 3));
+    assert_eq!(values(&list), [0, 1, 4, 2, 3]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_is_sorted_by()
+  {
+    let (sorted, a) = list_from(&[1, 2, 3]);
+    let (unsorted, b) = list_from(&[1, 3, 2]);
+    let (single, c) = list_from(&[1]);
+    assert!(sorted.is_sorted_by(|a, b| a <= b));
+    assert!(!unsorted.is_sorted_by(|a, b| a <= b));
+    assert!(!sorted.is_sorted_by(|a, b| a >= b));
+    assert!(single.is_sorted_by(|_, _| false));
+    assert!(List::<usize>::new().is_sorted_by(|_, _| false));
+
+    free_nodes(a);
+    free_nodes(b);
+    free_nodes(c);
+  }
+
+  #[test]
+  fn test_cursor_drain_forward()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4, 5]);
+    let mut drained = Vec::new();
+    let mut cursor = list.cursor_mut();
+    for _ in 0..4
+    {
+      cursor.move_next();
+    }
+    cursor.drain_forward(|node| drained.push(node));
+    assert_eq!(cursor.current_value(), None);
+    assert_eq!(drained, &nodes[3..]);
+    assert_eq!(values(&list), [0, 1, 2]);
+    unsafe { assert_eq!(drained[0].as_ref().prev_node(), None) };
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_get_two_mut()
+  {
+    let (mut list, nodes) = list_from(&[10, 20, 30, 40]);
+    let (a, b) = list.get_two_mut(1, 3).unwrap();
+    core::mem::swap(a, b);
+    assert_eq!(values(&list), [10, 40, 30, 20]);
+    assert!(list.get_two_mut(2, 2).is_none());
+    assert!(list.get_two_mut(0, 4).is_none());
+    assert!(list.get_two_mut(9, 1).is_none());
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_remaining()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    assert_eq!(cursor.remaining(), 5);
+    cursor.move_next();
+    assert_eq!(cursor.remaining(), 4);
+    cursor.move_next();
+    cursor.move_next();
+    assert_eq!(cursor.remaining(), 2);
+    cursor.move_next();
+    cursor.move_next();
+    assert_eq!(cursor.current_value(), Some(&mut 4));
+    assert_eq!(cursor.remaining(), 0);
+
+    // Same answer when the index has to be worked out
+    cursor.idx = None;
+    assert_eq!(cursor.remaining(), 0);
+    cursor.move_prev();
+    assert_eq!(cursor.remaining(), 1);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_swap_lists()
+  {
+    let (mut a, a_nodes) = list_from(&[1, 2, 3]);
+    let (mut b, b_nodes) = list_from(&[4, 5, 6, 7, 8]);
+    List::swap_lists(&mut a, &mut b);
+    assert_eq!(a.len(), 5);
+    assert_eq!(b.len(), 3);
+    assert_eq!(values(&a), [4, 5, 6, 7, 8]);
+    assert_eq!(values(&b), [1, 2, 3]);
+
+    let mut empty = List::new();
+    List::swap_lists(&mut a, &mut empty);
+    assert!(a.is_empty());
+    assert_eq!(values(&empty), [4, 5, 6, 7, 8]);
+
+    free_nodes(a_nodes);
+    free_nodes(b_nodes);
+  }
+
+  #[test]
+  fn test_insert_sorted()
+  {
+    let (mut list, mut nodes) = list_from(&[1, 3, 5]);
+    let extra = [alloc_node(4), alloc_node(0), alloc_node(9), alloc_node(3)];
+    for node in extra
+    {
+      list.insert_sorted(node, |a, b| a.cmp(b));
+    }
+    assert_eq!(values(&list), [0, 1, 3, 3, 4, 5, 9]);
+    assert_eq!(list.front, Some(extra[1]));
+    assert_eq!(list.back, Some(extra[2]));
+    // The new 3 went after the one already there
+    assert_eq!(list.node_at(3), Some(extra[3]));
+    nodes.extend(extra);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_remove_if()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    let mut removed = Vec::new();
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    while cursor.current_value().is_some()
+    {
+      let idx = cursor.idx;
+      if let Some(node) = cursor.remove_if(|x| *x % 2 == 0)
+      {
+        removed.push(node);
+        assert_eq!(cursor.idx, idx.filter(|_| cursor.current.is_some()));
+      }
+    }
+    assert_eq!(values(&list), [1, 3, 5, 7]);
+    assert_eq!(removed, [nodes[1], nodes[3], nodes[5], nodes[7]]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_front_back_mut()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4]);
+    let (front, back) = list.front_back_mut();
+    let (front, back) = (front.unwrap(), back.unwrap());
+    *front += *back;
+    *back = 0;
+    assert_eq!(values(&list), [5, 2, 3, 0]);
+
+    let (mut single, single_nodes) = list_from(&[7]);
+    assert_eq!(single.front_back_mut(), (Some(&mut 7), None));
+    assert_eq!(List::<usize>::new().front_back_mut(), (None, None));
+
+    free_nodes(nodes);
+    free_nodes(single_nodes);
+  }
+
+  #[test]
+  fn test_position()
+  {
+    let (list, nodes) = list_from(&[1, 4, 3, 6, 5, 7]);
+    assert_eq!(list.position(|&x| x % 2 == 0), Some((1, nodes[1])));
+    assert_eq!(list.rposition(|&x| x % 2 == 0), Some((3, nodes[3])));
+    assert_eq!(list.rposition(|&x| x == 1), Some((0, nodes[0])));
+    assert_eq!(list.rposition(|&x| x == 7), Some((5, nodes[5])));
+    assert_eq!(list.position(|&x| x > 10), None);
+    assert_eq!(list.rposition(|&x| x > 10), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_splice_node()
+  {
+    let (mut list, mut nodes) = list_from(&[1, 2, 3]);
+    let extra = [alloc_node(10), alloc_node(20)];
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.move_next();
+    assert_eq!(cursor.splice_node_before(extra[0]), extra[0]);
+    assert_eq!(cursor.splice_node_after(extra[1]), extra[1]);
+    assert_eq!(values(&list), [1, 10, 2, 20, 3]);
+    nodes.extend(extra);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "still linked")]
+  fn test_cursor_splice_linked_node()
+  {
+    let mut storage = [1, 2, 3].map(Node::new);
+    let mut other_storage = [4, 5].map(Node::new);
+    let (mut list, _) = list_on_stack(&mut storage);
+    let (_other, other_nodes) = list_on_stack(&mut other_storage);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.splice_node_before(other_nodes[0]);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  fn test_map()
+  {
+    let (list, nodes) = list_from(&[1, 2, 3]);
+    let mapped = list.map(|x: &i32| x * 10);
+    assert_eq!(values(&mapped), [10, 20, 30]);
+    assert_eq!(values(&list), [1, 2, 3]);
+    let strings = list.map(|x| format!("#{x}"));
+    assert_eq!(strings.front_val().map(String::as_str), Some("#1"));
+    assert_eq!(strings.len(), 3);
+
+    free_boxed(mapped);
+    free_boxed(strings);
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_neighbors_mut()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    assert!(cursor.neighbors_mut().is_none());
+    cursor.move_next();
+    let (prev, current, next) = cursor.neighbors_mut().unwrap();
+    assert_eq!((prev, *current, next), (None, 1, Some(&mut 2)));
+
+    cursor.move_next();
+    cursor.move_next();
+    let (prev, current, next) = cursor.neighbors_mut().unwrap();
+    assert_eq!((&prev, &next), (&Some(&mut 2), &Some(&mut 4)));
+    *current = *prev.unwrap() + *next.unwrap();
+    assert_eq!(values(&list), [1, 2, 6, 4]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_clear()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3]);
+    assert_eq!(list.clear_fast(), (Some(nodes[0]), Some(nodes[2])));
+    assert_eq!(list.len(), 0);
+    assert_eq!(values(&list), []);
+    // The old chain is left as it was
+    unsafe { assert_eq!(nodes[0].as_ref().next_node(), Some(nodes[1])) };
+    assert_eq!(List::<usize>::new().clear_fast(), (None, None));
+
+    let (mut list, more) = list_from(&[4, 5, 6]);
+    list.clear();
+    assert!(list.is_empty());
+    for node in &more
+    {
+      unsafe { assert!(!node.as_ref().is_linked()) };
+    }
+
+    free_nodes(nodes);
+    free_nodes(more);
+  }
+
+  #[test]
+  fn test_cursor_scan_mut()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    let total = cursor.scan_mut(0, |sum, x| {
+      *sum += *x;
+      *x = *sum;
+    });
+    assert_eq!(total, 10);
+    assert_eq!(cursor.current_value(), Some(&mut 1));
+    assert_eq!(values(&list), [1, 3, 6, 10]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_node_index()
+  {
+    let (list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    assert!(list.iter_nodes().eq(nodes.iter().copied()));
+    let middle = list.iter_nodes().nth(2).unwrap();
+    assert_eq!(list.node_index(middle), Some(2));
+    assert_eq!(list.node_index(nodes[4]), Some(4));
+
+    let stray = alloc_node(9);
+    assert_eq!(list.node_index(stray), None);
+
+    free_nodes(nodes);
+    free_nodes(vec![stray]);
+  }
+
+  #[test]
+  fn test_cursor_insert_slice_before()
+  {
+    let (mut list, mut nodes) = list_from(&[0, 1, 2, 3]);
+    let extra: Vec<_> = (10..13).map(alloc_node).collect();
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.move_next();
+    cursor.move_next();
+    cursor.insert_slice_before(&extra);
+    cursor.insert_slice_before(&[]);
+    assert_eq!(cursor.current_index(), Some(5));
+    assert_eq!(cursor.current_value(), Some(&mut 2));
+    assert_eq!(values(&list), [0, 1, 10, 11, 12, 2, 3]);
+    nodes.extend(extra);
+
+    // At the front the list's front moves too
+    let (mut list, mut front_nodes) = list_from(&[5]);
+    let extra: Vec<_> = (1..3).map(alloc_node).collect();
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.insert_slice_before(&extra);
+    assert_eq!(cursor.current_index(), Some(2));
+    assert_eq!(values(&list), [1, 2, 5]);
+    front_nodes.extend(extra);
+
+    free_nodes(nodes);
+    free_nodes(front_nodes);
+  }
+
+  #[test]
+  fn test_retain()
+  {
+    // Decrement a ttl, dropping whatever hits zero
+    let (mut list, nodes) = list_from(&[3, 1, 2, 1, 5]);
+    let expired = list.retain_mut(|ttl| {
+      *ttl -= 1;
+      *ttl > 0
+    });
+    assert_eq!(values(&list), [2, 1, 4]);
+    assert_eq!(values(&expired), [0, 0]);
+    assert_eq!(expired.front, Some(nodes[1]));
+
+    let odd = list.retain(|&x| x % 2 == 0);
+    assert_eq!(values(&list), [2, 4]);
+    assert_eq!(values(&odd), [1]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_iter()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    assert!(list.iter().eq(&[0, 1, 2, 3, 4]));
+    assert_eq!(list.iter().len(), 5);
+
+    let mut iter = list.iter();
+    assert_eq!(iter.nth(3), Some(&3));
+    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.next(), Some(&4));
+    assert_eq!(iter.next(), None);
+    assert_eq!(list.iter().nth(5), None);
+
+    for x in list.iter_mut()
+    {
+      *x *= 2;
+    }
+    let mut iter = list.iter_mut();
+    assert_eq!(iter.nth(3), Some(&mut 6));
+    assert_eq!(iter.next(), Some(&mut 8));
+    assert_eq!(iter.nth(1), None);
+    assert_eq!(values(&list), [0, 2, 4, 6, 8]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_step()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4, 5]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.step(3);
+    assert_eq!(cursor.current_index(), Some(3));
+    cursor.step(-2);
+    assert_eq!(cursor.current_index(), Some(1));
+    assert_eq!(cursor.current_value(), Some(&mut 1));
+    cursor.step(0);
+    assert_eq!(cursor.current_index(), Some(1));
+
+    // Running off either end parks on the ghost
+    cursor.step(100);
+    assert_eq!(cursor.current_index(), None);
+    cursor.step(-2);
+    assert_eq!(cursor.current_value(), Some(&mut 4));
+    cursor.step(-100);
+    assert_eq!(cursor.current_index(), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_node_links()
+  {
+    let (_list, nodes) = list_from(&[0, 1, 2]);
+    unsafe {
+      assert_eq!(nodes[1].as_ref().links(), (Some(nodes[0]), Some(nodes[2])));
+      assert_eq!(nodes[0].as_ref().links(), (None, Some(nodes[1])));
+      assert_eq!(nodes[2].as_ref().links(), (Some(nodes[1]), None));
+    }
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_fast_forward()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.fast_forward();
+    assert_eq!(cursor.current_index(), Some(3));
+    assert_eq!(
+      cursor.current_value().copied(),
+      cursor.list.back_val().copied()
+    );
+    cursor.move_next();
+    assert_eq!(cursor.current_index(), None);
+
+    let mut empty = List::<usize>::new();
+    let mut cursor = empty.cursor_mut();
+    cursor.fast_forward();
+    assert_eq!(cursor.current_index(), None);
+    assert_eq!(cursor.current_value(), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_drain_back_while()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 0, 0, 0]);
+    let mut drained = Vec::new();
+    list.drain_back_while(|&x| x == 0, |node| drained.push(node));
+    assert_eq!(drained, [nodes[4], nodes[3], nodes[2]]);
+    assert_eq!(values(&list), [1, 2]);
+
+    // Everything matching empties the list
+    list.drain_back_while(|_| true, |node| drained.push(node));
+    assert!(list.is_empty());
+    assert_eq!(drained.len(), 5);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_repair_from_front()
+  {
+    // Only the next links are set up, like a singly linked list would
+    let nodes: Vec<_> = (0..4).map(alloc_node).collect();
+    for pair in nodes.windows(2)
+    {
+      unsafe { (*pair[0].as_ptr()).next = Some(pair[1]) };
+    }
+    let mut list = unsafe { List::from_parts(Some(nodes[0]), None, 0) };
+    unsafe { list.repair_from_front() };
+    assert_eq!(list.len(), 4);
+    assert_eq!(list.back, Some(nodes[3]));
+    assert_eq!(values(&list), [0, 1, 2, 3]); // values runs check_invariants
+    assert_eq!(list.pop_back(), Some(nodes[3]));
+    assert_eq!(list.back_val(), Some(&2));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_neighbors()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3]);
+    let mut cursor = list.cursor_mut();
+    assert_eq!(cursor.neighbors(), (None, None, None));
+    cursor.move_next();
+    assert_eq!(cursor.neighbors(), (None, Some(&1), Some(&2)));
+    cursor.move_next();
+    let (prev, current, next) = cursor.neighbors();
+    let also = cursor.neighbors(); // shared borrows, so both can be held at once
+    assert_eq!((prev, current, next), (Some(&1), Some(&2), Some(&3)));
+    assert_eq!(also, (prev, current, next));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_count_until()
+  {
+    let (list, nodes) = list_from(&[3, 8, 10, 11, 4, 20]);
+    assert_eq!(list.count_until(|&x| x > 10), Some(3));
+    assert_eq!(list.count_until(|&x| x == 3), Some(0));
+    assert_eq!(list.count_until(|&x| x > 100), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_reduce()
+  {
+    let (list, nodes) = list_from(&[1, 2, 3, 4]);
+    assert_eq!(list.reduce(|a, b| a + b), Some(Ok(10)));
+    assert_eq!(list.reduce(|a, b| *a.max(b)), Some(Ok(4)));
+    let (single, single_nodes) = list_from(&[7]);
+    assert_eq!(single.reduce(|a, b| a + b), Some(Err(&7)));
+    assert_eq!(List::<usize>::new().reduce(|a, b| a + b), None);
+
+    // No Clone needed
+    #[derive(Debug, PartialEq)]
+    struct Len(usize);
+    let mut words = List::new();
+    let word_nodes = [alloc_node(Len(2)), alloc_node(Len(3)), alloc_node(Len(5))];
+    words.extend(word_nodes);
+    assert_eq!(words.reduce(|a, b| Len(a.0 + b.0)), Some(Ok(Len(10))));
+
+    free_nodes(nodes);
+    free_nodes(single_nodes);
+    free_nodes(word_nodes.to_vec());
+  }
+
+  #[test]
+  fn test_cursor_ghost_insert()
+  {
+    let (mut list, mut nodes) = list_from(&[1, 2]);
+    let extra = [alloc_node(10), alloc_node(20), alloc_node(30)];
+    let slice = [alloc_node(40), alloc_node(50)];
+    let mut cursor = list.cursor_mut();
+
+    // Fresh cursor sits on the ghost, before it is the back and after it is the front
+    cursor.insert_before(extra[0]);
+    cursor.insert_after(extra[1]);
+    assert_eq!(cursor.current_index(), None);
+    assert_eq!(values(cursor.list), [20, 1, 2, 10]);
+    cursor.insert_slice_before(&slice);
+    assert_eq!(values(cursor.list), [20, 1, 2, 10, 40, 50]);
+
+    // Actually positioned on the front, insert_before goes in ahead of it
+    cursor.move_to_front();
+    assert_eq!(cursor.current_index(), Some(0));
+    cursor.insert_before(extra[2]);
+    assert_eq!(cursor.current_index(), Some(1));
+    assert_eq!(cursor.current_value(), Some(&mut 20));
+    assert_eq!(values(&list), [30, 20, 1, 2, 10, 40, 50]);
+    nodes.extend(extra);
+    nodes.extend(slice);
+
+    let mut empty = List::<usize>::new();
+    let mut cursor = empty.cursor_mut();
+    cursor.move_to_front();
+    assert_eq!(cursor.current_index(), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_multiset_eq()
+  {
+    let (a, a_nodes) = list_from(&[1, 2, 3]);
+    let (b, b_nodes) = list_from(&[3, 1, 2]);
+    let (c, c_nodes) = list_from(&[1, 2, 2]);
+    let (d, d_nodes) = list_from(&[2, 1, 2]);
+    assert!(a.multiset_eq(&b));
+    assert!(!c.multiset_eq(&a));
+    assert!(c.multiset_eq(&d));
+    assert!(!a.multiset_eq(&List::new()));
+
+    free_nodes(a_nodes);
+    free_nodes(b_nodes);
+    free_nodes(c_nodes);
+    free_nodes(d_nodes);
+  }
+
+  #[test]
+  fn test_cursor_replace_next_n()
+  {
+    let (mut list, mut nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let (mut replacement, more) = list_from(&[10, 11, 12]);
+    nodes.extend(more);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.move_next();
+    let removed = cursor.replace_next_n(2, &mut replacement);
+    assert_eq!(cursor.current_index(), Some(1));
+    assert_eq!(cursor.current_value(), Some(&mut 10));
+    assert_eq!(values(&removed), [1, 2]);
+    assert!(replacement.is_empty());
+    assert_eq!(list.len(), 6);
+    assert_eq!(values(&list), [0, 10, 11, 12, 3, 4]);
+
+    // Asking for more than is left takes the rest, an empty replacement just removes
+    let mut cursor = list.cursor_mut();
+    cursor.move_to_front();
+    cursor.step(4);
+    let removed = cursor.replace_next_n(10, &mut replacement);
+    assert_eq!(values(&removed), [3, 4]);
+    assert_eq!(cursor.current_index(), None);
+    assert_eq!(values(&list), [0, 10, 11, 12]);
+
+    // From the ghost the replacement is appended
+    let (mut tail, tail_nodes) = list_from(&[20]);
+    let mut cursor = list.cursor_mut();
+    assert!(cursor.replace_next_n(3, &mut tail).is_empty());
+    assert_eq!(cursor.current_index(), Some(4));
+    assert_eq!(cursor.current_value(), Some(&mut 20));
+    assert_eq!(values(&list), [0, 10, 11, 12, 20]);
+    nodes.extend(tail_nodes);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_len_minus_one()
+  {
+    assert_eq!(List::<usize>::new().len_minus_one(), None);
+    let (mut list, nodes) = list_from(&[1, 2, 3]);
+    assert_eq!(list.len_minus_one(), Some(2));
+    list.pop_back();
+    list.pop_back();
+    assert_eq!(list.len_minus_one(), Some(0));
+    list.pop_back();
+    assert_eq!(list.len_minus_one(), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_visit_forward()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut seen = Vec::new();
+    let mut cursor = list.cursor_mut();
+    cursor.visit_forward(|&x| seen.push(x)); // ghost, nothing to visit
+    cursor.move_to_front();
+    cursor.step(2);
+    cursor.visit_forward(|&x| seen.push(x));
+    assert_eq!(seen, [2, 3, 4]);
+    assert_eq!(cursor.current_index(), None);
+    assert_eq!(values(&list), [0, 1, 2, 3, 4]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_interleave()
+  {
+    let (mut a, mut nodes) = list_from(&[1, 3, 5]);
+    let (mut b, b_nodes) = list_from(&[2, 4, 6, 8]);
+    nodes.extend(b_nodes);
+    a.interleave(&mut b);
+    assert_eq!(values(&a), [1, 2, 3, 4, 5, 6, 8]);
+    assert!(b.is_empty());
+
+    // Running out of other first leaves our tail alone
+    let (mut c, c_nodes) = list_from(&[10, 20, 30]);
+    let (mut d, d_nodes) = list_from(&[15]);
+    c.interleave(&mut d);
+    assert_eq!(values(&c), [10, 15, 20, 30]);
+    let mut empty = List::new();
+    empty.interleave(&mut c);
+    assert_eq!(values(&empty), [10, 15, 20, 30]);
+
+    free_nodes(nodes);
+    free_nodes(c_nodes);
+    free_nodes(d_nodes);
+  }
+
+  #[test]
+  fn test_cursor_split_after()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_to_front();
+    cursor.step(1);
+    let tail = cursor.split_after();
+    assert_eq!(cursor.current_value(), Some(&mut 1));
+    assert!(cursor.split_after().is_empty()); // already on the back now
+    assert_eq!(values(&tail), [2, 3, 4]);
+    assert_eq!(values(&list), [0, 1]);
+
+    let mut cursor = list.cursor_mut();
+    let all = cursor.split_after();
+    assert_eq!(values(&all), [0, 1]);
+    assert!(list.is_empty());
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_bisect()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_to_front();
+    cursor.step(2);
+    let (mut front, back) = cursor.bisect();
+    assert!(list.is_empty());
+    assert_eq!((front.len(), back.len()), (3, 2));
+    assert_eq!(values(&front), [0, 1, 2]);
+    assert_eq!(values(&back), [3, 4]);
+
+    let (whole, rest) = front.cursor_mut().bisect();
+    assert_eq!(values(&whole), [0, 1, 2]);
+    assert!(rest.is_empty());
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_node_replace_elem()
+  {
+    let mut node = Node::new(String::from("old"));
+    assert_eq!(node.replace_elem(String::from("new")), "old");
+    assert_eq!(node.elem(), "new");
+  }
+
+  #[test]
+  fn test_iter_clone()
+  {
+    let (list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut iter = list.iter();
+    iter.next();
+    iter.next();
+    let mut fork = iter.clone();
+    assert_eq!(fork.next(), Some(&2));
+    assert!(iter.clone().eq(&[2, 3, 4]));
+    assert!(fork.eq(&[3, 4]));
+    assert!(iter.eq(&[2, 3, 4]));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_set_current()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.set_current(nodes[2]);
+    assert_eq!(cursor.current_index(), None);
+    assert_eq!(cursor.current_value(), Some(&mut 2));
+    cursor.move_next();
+    assert_eq!(cursor.current_index(), None);
+    assert_eq!(cursor.current_value(), Some(&mut 3));
+
+    cursor.set_current_with_index(nodes[1], 1);
+    assert_eq!(cursor.current_index(), Some(1));
+    cursor.move_next();
+    assert_eq!(cursor.current_index(), Some(2));
+    assert_eq!(cursor.current_value(), Some(&mut 2));
+    cursor.move_prev();
+    cursor.move_prev();
+    assert_eq!(cursor.current_index(), Some(0));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_insert_sorted_by_key()
+  {
+    let (mut list, mut nodes) = list_from(&[(1, 'a'), (3, 'b'), (5, 'c')]);
+    let extra = [
+      alloc_node((4, 'd')),
+      alloc_node((0, 'e')),
+      alloc_node((9, 'f')),
+      alloc_node((3, 'g')),
+    ];
+    for node in extra
+    {
+      list.insert_sorted_by_key(node, |x| x.0);
+    }
+    assert_eq!(
+      values(&list),
+      [
+        (0, 'e'),
+        (1, 'a'),
+        (3, 'b'),
+        (3, 'g'),
+        (4, 'd'),
+        (5, 'c'),
+        (9, 'f')
+      ]
+    );
+    nodes.extend(extra);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_remove_stay()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_to_front();
+    cursor.step(2);
+    assert_eq!(cursor.remove(), Some(nodes[2]));
+    assert_eq!(cursor.current_index(), Some(2));
+    assert_eq!(cursor.current_value(), Some(&mut 3));
+
+    assert_eq!(cursor.remove_stay(), Some(nodes[3]));
+    assert_eq!(cursor.current_index(), Some(1));
+    assert_eq!(cursor.current_value(), Some(&mut 1));
+
+    cursor.move_to_front();
+    assert_eq!(cursor.remove_stay(), Some(nodes[0]));
+    assert_eq!(cursor.current_index(), None);
+    assert_eq!(cursor.remove_stay(), None);
+    assert_eq!(values(&list), [1, 4]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  fn test_split_every()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    let pages = list.split_every(3);
+    assert!(list.is_empty());
+    let sizes: Vec<_> = pages.iter().map(List::len).collect();
+    assert_eq!(sizes, [3, 3, 3, 1]);
+    assert_eq!(values(&pages[1]), [3, 4, 5]);
+    assert_eq!(values(&pages[3]), [9]);
+    assert!(list.split_every(3).is_empty());
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  #[should_panic]
+  fn test_split_every_zero()
+  {
+    List::<usize>::new().split_every(0);
+  }
+
+  #[test]
+  fn test_cursor_front_back()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_to_front();
+    cursor.step(2);
+    assert_eq!(cursor.front(), Some(&1));
+    assert_eq!(cursor.back(), Some(&4));
+    assert_eq!(cursor.neighbors().1, Some(&3));
+
+    let mut empty = List::<usize>::new();
+    let cursor = empty.cursor_mut();
+    assert_eq!((cursor.front(), cursor.back()), (None, None));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_rotate_to_node()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    list.rotate_to_node(nodes[2]);
+    assert_eq!(values(&list), [2, 3, 4, 0, 1]);
+    list.rotate_to_node(nodes[1]);
+    assert_eq!(values(&list), [1, 2, 3, 4, 0]);
+    list.rotate_to_node(nodes[1]);
+    assert_eq!(values(&list), [1, 2, 3, 4, 0]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_fold_nodes()
+  {
+    let (list, nodes) = list_from(&[5, 6, 7]);
+    let folded = list.fold_nodes(Vec::new(), |mut acc, node| {
+      acc.push(node);
+      acc
+    });
+    assert_eq!(folded, list.iter_nodes().collect::<Vec<_>>());
+    assert_eq!(folded, nodes);
+    let index = list.fold_nodes(std::collections::HashMap::new(), |mut map, node| {
+      map.insert(map.len(), node);
+      map
+    });
+    assert_eq!(index[&1], nodes[1]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_next_value()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3]);
+    let mut seen = Vec::new();
+    let mut cursor = list.cursor_mut();
+    while let Some(x) = cursor.next_value()
+    {
+      seen.push(*x);
+      *x *= 10;
+    }
+    assert_eq!(seen, [1, 2, 3]);
+    assert_eq!(cursor.current_index(), None);
+    assert_eq!(values(&list), [10, 20, 30]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_contains_node()
+  {
+    let (list, nodes) = list_from(&[1, 2, 3]);
+    let stray = alloc_node(2);
+    assert!(nodes.iter().all(|&node| list.contains_node(node)));
+    assert!(!list.contains_node(stray));
+    assert!(!List::new().contains_node(nodes[0]));
+
+    free_nodes(nodes);
+    free_nodes(vec![stray]);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  fn test_indices_where()
+  {
+    let (list, nodes) = list_from(&[1, 2, 3, 4, 5, 6]);
+    assert_eq!(list.indices_where(|&x| x % 2 == 0), [1, 3, 5]);
+    assert!(list.indices_where(|&x| x > 6).is_empty());
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  fn test_flatten()
+  {
+    let (a, mut nodes) = list_from(&[1, 2]);
+    let (b, b_nodes) = list_from(&[3]);
+    let (c, c_nodes) = list_from(&[4, 5, 6]);
+    nodes.extend(b_nodes);
+    nodes.extend(c_nodes);
+
+    let mut outer = List::new();
+    for inner in [a, List::new(), b, c]
+    {
+      outer.push_back(NonNull::from(Box::leak(Box::new(Node::new(inner)))));
+    }
+    let flat = unsafe { outer.flatten() };
+    assert_eq!(flat.len(), 6);
+    assert_eq!(values(&flat), [1, 2, 3, 4, 5, 6]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_with_next_n_mut()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4, 5]);
+    let mut cursor = list.cursor_mut();
+    cursor.with_next_n_mut(|_, _, _| panic!("called on the ghost"));
+    cursor.move_to_front();
+    while cursor.move_next_if(|_| true)
+    {
+      cursor.with_next_n_mut(|current, prev, next| {
+        if let (Some(prev), Some(next)) = (prev, next)
+        {
+          *current = *prev + *next;
+        }
+      });
+    }
+    assert_eq!(values(&list), [1, 4, 8, 13, 5]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[cfg(feature = "alloc")]
+  fn test_dedup_all()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 1, 3, 2, 4]);
+    let removed = list.dedup_all();
+    assert_eq!(values(&list), [1, 2, 3, 4]);
+    assert_eq!(removed.len(), 2);
+    assert_eq!(removed.front, Some(nodes[2]));
+    assert_eq!(removed.back, Some(nodes[4]));
+    assert!(list.dedup_all().is_empty());
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_insert_before_if_absent()
+  {
+    let (mut list, mut nodes) = list_from(&[1, 3, 5]);
+    let dup = alloc_node(5);
+    let new = alloc_node(2);
+    let mut cursor = list.cursor_mut();
+    cursor.move_to_front();
+    cursor.move_next();
+    assert!(!cursor.insert_before_if_absent(dup, |a, b| a == b));
+    assert!(cursor.insert_before_if_absent(new, |a, b| a == b));
+    assert_eq!(cursor.current_index(), Some(2));
+    assert_eq!(values(&list), [1, 2, 3, 5]);
+    unsafe { assert!(!dup.as_ref().is_linked()) };
+    nodes.extend([dup, new]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_with_node()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3]);
+    assert_eq!(list.front_with_node(), Some((&1, nodes[0])));
+    assert_eq!(list.back_with_node(), Some((&3, nodes[2])));
+    assert_eq!(list.front_node(), Some(nodes[0]));
+    assert_eq!(list.back_node(), Some(nodes[2]));
+    let (_, front) = list.front_with_node().unwrap();
+    assert_eq!(list.pop_front(), Some(front));
+    list.clear();
+    assert_eq!(list.front_with_node(), None);
+    assert_eq!(list.back_with_node(), None);
+
+    free_nodes(nodes);
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn test_take_current()
+  {
+    use core::cell::Cell;
+
+    struct Counted<'a>(u32, &'a Cell<u32>);
+    impl Drop for Counted<'_>
+    {
+      fn drop(&mut self)
+      {
+        self.1.set(self.1.get() + 1);
+      }
+    }
+
+    let drops = Cell::new(0);
+    let mut list: List<Counted> = (0..6)
+      .map(|x| NonNull::from(Box::leak(Box::new(Node::new(Counted(x, &drops))))))
+      .collect();
+    let mut taken = Vec::new();
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    while cursor.current_link().is_some()
+    {
+      if cursor.current_value().unwrap().0.is_multiple_of(2)
+      {
+        taken.push(unsafe { cursor.take_current() }.unwrap());
+      }
+      else
+      {
+        cursor.move_next();
+      }
+    }
+    assert_eq!(unsafe { cursor.take_current() }.map(|x| x.0), None);
+    assert_eq!(drops.get(), 0);
+    assert_eq!(taken.iter().map(|x| x.0).collect::<Vec<_>>(), [0, 2, 4]);
+    assert_eq!(list.iter().map(|x| x.0).collect::<Vec<_>>(), [1, 3, 5]);
+    list.check_invariants();
+
+    drop(taken);
+    assert_eq!(drops.get(), 3);
+    free_boxed(list);
+    assert_eq!(drops.get(), 6);
+  }
+
+  #[test]
+  fn test_binary_search_by()
+  {
+    let input: Vec<usize> = (0..100).map(|x| x * 2).collect();
+    let (list, nodes) = list_from(&input);
+    for (i, x) in input.iter().enumerate()
+    {
+      assert_eq!(list.binary_search_by(|y| y.cmp(x)), Ok((i, nodes[i])));
+      assert_eq!(list.binary_search_by(|y| y.cmp(&(x + 1))), Err(i + 1));
+    }
+    assert_eq!(list.binary_search_by(|y| y.cmp(&0)), Ok((0, nodes[0])));
+    assert_eq!(list.binary_search_by(|y| y.cmp(&500)), Err(100));
+    assert_eq!(List::<usize>::new().binary_search_by(|y| y.cmp(&0)), Err(0));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_advance()
+  {
+    let (mut list, nodes) = list_from(&[4, 5, 6]);
+    let mut cursor = list.cursor_mut();
+    let mut seen = Vec::new();
+    while let Some(x) = cursor.advance()
+    {
+      seen.push(*x);
+    }
+    assert_eq!(seen, [4, 5, 6]);
+    assert_eq!(cursor.idx, None);
+    assert_eq!(cursor.advance(), Some(&4));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_map_in_place_same_size()
+  {
+    let (list, nodes) = list_from(&[0u32, 1, 2, u32::MAX]);
+    let mapped: List<i32> = list.map_in_place_same_size(|x| x as i32 - 1);
+    assert_eq!(values(&mapped), [-1, 0, 1, -2]);
+    assert_eq!(mapped.front_node().map(NonNull::cast), Some(nodes[0]));
+    assert_eq!(mapped.back_node().map(NonNull::cast), Some(nodes[3]));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_map_in_place_same_size_mismatch()
+  {
+    let list: List<u32> = List::new();
+    let _ = list.map_in_place_same_size(|x| x as u64);
+  }
+
+  #[test]
+  fn test_splice_before_reversed()
+  {
+    let (mut list, nodes) = list_from(&[10, 20]);
+    let (mut other, other_nodes) = list_from(&[1, 2, 3]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.move_next();
+    cursor.splice_before_reversed(&mut other);
+    assert_eq!(cursor.current_index(), Some(4));
+    assert_eq!(cursor.current_value(), Some(&mut 20));
+    cursor.splice_before_reversed(&mut List::new());
+    assert_eq!(values(&list), [10, 3, 2, 1, 20]);
+    assert!(other.is_empty());
+
+    // Off the ghost they land on the back
+    let (mut other, more_nodes) = list_from(&[7, 8]);
+    list.cursor_mut().splice_before_reversed(&mut other);
+    assert_eq!(values(&list), [10, 3, 2, 1, 20, 8, 7]);
+
+    free_nodes(nodes);
+    free_nodes(other_nodes);
+    free_nodes(more_nodes);
+  }
+
+  #[test]
+  fn test_ends()
+  {
+    assert_eq!(List::<usize>::new().ends(), None);
+
+    let (single, single_nodes) = list_from(&[4]);
+    assert_eq!(single.ends(), Some((&4, &4)));
+
+    let (list, nodes) = list_from(&[1, 2, 3]);
+    assert_eq!(list.ends(), Some((&1, &3)));
+
+    free_nodes(single_nodes);
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_rewind_to_run_start()
+  {
+    let (mut list, nodes) = list_from(&[1, 5, 5, 5, 2]);
+    let mut cursor = list.cursor_mut();
+    cursor.step(4);
+    cursor.rewind_to_run_start(|a, b| a == b);
+    assert_eq!(cursor.current_link(), Some(nodes[1]));
+    assert_eq!(cursor.current_index(), Some(1));
+
+    // Already at the start of its run
+    cursor.move_prev();
+    cursor.rewind_to_run_start(|a, b| a == b);
+    assert_eq!(cursor.current_link(), Some(nodes[0]));
+
+    // Runs reaching the front stop there
+    cursor.rewind_to_run_start(|_, _| true);
+    assert_eq!(cursor.current_index(), Some(0));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_split_at_first()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4]);
+    let tail = list.split_at_first(|x| *x >= 3).unwrap();
+    assert_eq!(values(&list), [1, 2]);
+    assert_eq!(values(&tail), [3, 4]);
+
+    assert!(list.split_at_first(|x| *x > 10).is_none());
+    assert_eq!(values(&list), [1, 2]);
+
+    let whole = list.split_at_first(|_| true).unwrap();
+    assert!(list.is_empty());
+    assert_eq!(values(&whole), [1, 2]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_count_run()
+  {
+    let (mut list, nodes) = list_from(&[7, 7, 7, 1]);
+    let mut cursor = list.cursor_mut();
+    assert_eq!(cursor.count_run(|a, b| a == b), 0);
+    cursor.move_next();
+    assert_eq!(cursor.count_run(|a, b| a == b), 3);
+    assert_eq!(cursor.current_index(), Some(0));
+    cursor.step(3);
+    assert_eq!(cursor.count_run(|a, b| a == b), 1);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_rotate()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4, 5]);
+    list.rotate(RotateDir::Left, 2);
+    assert_eq!(values(&list), [3, 4, 5, 1, 2]);
+    list.rotate(RotateDir::Right, 2);
+    assert_eq!(values(&list), [1, 2, 3, 4, 5]);
+
+    list.rotate(RotateDir::Right, 7);
+    assert_eq!(values(&list), [4, 5, 1, 2, 3]);
+    list.rotate(RotateDir::Left, 5);
+    assert_eq!(values(&list), [4, 5, 1, 2, 3]);
+
+    let mut empty = List::<usize>::new();
+    empty.rotate(RotateDir::Left, 3);
+    assert!(empty.is_empty());
+    let (mut single, single_nodes) = list_from(&[9]);
+    single.rotate(RotateDir::Right, 3);
+    assert_eq!(values(&single), [9]);
+
+    free_nodes(nodes);
+    free_nodes(single_nodes);
+  }
+
+  #[test]
+  fn test_swap_runs()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4, 5]);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.swap_runs(2, 2);
+    assert_eq!(cursor.current_value(), Some(&mut 1));
+    assert_eq!(cursor.current_index(), Some(2));
+    assert_eq!(values(&list), [3, 4, 1, 2, 5]);
+
+    // Blocks reaching the back
+    let mut cursor = list.cursor_mut();
+    cursor.step(3);
+    cursor.swap_runs(1, 2);
+    assert_eq!(values(&list), [3, 4, 2, 5, 1]);
+
+    // Whole list, single nodes
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.swap_runs(4, 1);
+    cursor.swap_runs(0, 3);
+    assert_eq!(values(&list), [1, 3, 4, 2, 5]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_swap_runs_too_long()
+  {
+    let mut storage = [1, 2, 3].map(Node::new);
+    let (mut list, _) = list_on_stack(&mut storage);
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.swap_runs(2, 2);
+  }
+
+  #[test]
+  fn test_from_raw_nodes()
+  {
+    let nodes: Vec<_> = (0..4).map(alloc_node).collect();
+    unsafe {
+      for pair in nodes.windows(2)
+      {
+        (*pair[0].as_ptr()).next = Some(pair[1]);
+        (*pair[1].as_ptr()).prev = Some(pair[0]);
+      }
+      assert!(List::try_from_raw_nodes(nodes[0], nodes[3], 3).is_none());
+      assert!(List::try_from_raw_nodes(nodes[0], nodes[3], 5).is_none());
+      assert!(List::try_from_raw_nodes(nodes[1], nodes[3], 3).is_none());
+      assert!(List::try_from_raw_nodes(nodes[0], nodes[2], 3).is_none());
+
+      let list = List::try_from_raw_nodes(nodes[0], nodes[3], 4).unwrap();
+      assert_eq!(values(&list), [0, 1, 2, 3]);
+      let round_trip = List::from_raw_nodes(list.front.unwrap(), list.back.unwrap(), list.len());
+      assert_eq!(values(&round_trip), [0, 1, 2, 3]);
+    }
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_value_at_offset()
+  {
+    let (mut list, nodes) = list_from(&[0, 1, 2, 3, 4]);
+    let mut cursor = list.cursor_mut();
+    cursor.step(3);
+    assert_eq!(cursor.value_at_offset(-1), Some(&1));
+    assert_eq!(cursor.value_at_offset(0), Some(&2));
+    assert_eq!(cursor.value_at_offset(2), Some(&4));
+    assert_eq!(cursor.value_at_offset(3), None);
+    assert_eq!(cursor.value_at_offset(-3), None);
+    assert_eq!(cursor.current_index(), Some(2));
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_min_max()
+  {
+    use rand::seq::SliceRandom;
+
+    let mut elems: Vec<usize> = (10..60).collect();
+    elems.shuffle(&mut rand::rng());
+    let (list, nodes) = list_from(&elems);
+    let (min, max) = list.min_max().unwrap();
+    assert_eq!((*min, *max), (10, 59));
+    let min_node = list.position(|&x| x == 10).unwrap().1;
+    assert!(core::ptr::eq(min, unsafe { min_node.as_ref().elem() }));
+
+    let (single, single_nodes) = list_from(&[3]);
+    assert_eq!(single.min_max(), Some((&3, &3)));
+    assert_eq!(List::<usize>::new().min_max(), None);
+
+    free_nodes(nodes);
+    free_nodes(single_nodes);
+  }
+
+  #[test]
+  fn test_move_to_nth_match()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4, 5, 6]);
+    let mut cursor = list.cursor_mut();
+    assert!(cursor.move_to_nth_match(1, |x| x % 2 == 0));
+    assert_eq!(cursor.current_value(), Some(&mut 4));
+    assert_eq!(cursor.current_index(), Some(3));
+
+    // The current element counts as the 0th match
+    assert!(cursor.move_to_nth_match(0, |x| x % 2 == 0));
+    assert_eq!(cursor.current_index(), Some(3));
+
+    assert!(!cursor.move_to_nth_match(2, |x| x % 2 == 0));
+    assert_eq!(cursor.current_link(), None);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_retain_first_matches()
+  {
+    let (mut list, nodes) = list_from(&[2, 1, 4, 3, 6, 5, 8]);
+    let removed = list.retain_first_matches(2, |x| x % 2 == 0);
+    assert_eq!(values(&list), [2, 1, 4, 3, 5]);
+    assert_eq!(values(&removed), [6, 8]);
+
+    let removed = list.retain_first_matches(0, |x| x % 2 == 1);
+    assert_eq!(values(&list), [2, 4]);
+    assert_eq!(values(&removed), [1, 3, 5]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_cursor_after_split_after()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4, 5]);
+    let mut cursor = list.cursor_mut();
+    cursor.step(2);
+    let tail = cursor.split_after();
+    assert_eq!(cursor.current_index(), Some(1));
+    cursor.move_next();
+    assert_eq!(cursor.current_link(), None);
+    assert_eq!(cursor.current_index(), None);
+    cursor.move_prev();
+    assert_eq!(cursor.current_link(), Some(nodes[1]));
+    assert_eq!(cursor.current_index(), Some(1));
+    cursor.move_prev();
+    cursor.move_prev();
+    cursor.move_prev();
+    assert_eq!(cursor.current_link(), Some(nodes[1]));
+    assert_eq!(values(&list), [1, 2]);
+    assert_eq!(values(&tail), [3, 4, 5]);
+
+    // Same with the index unknown
+    let mut cursor = list.cursor_mut();
+    cursor.set_current(nodes[0]);
+    let tail_2 = cursor.split_after();
+    cursor.move_next();
+    assert_eq!(cursor.current_link(), None);
+    cursor.move_next();
+    assert_eq!(cursor.current_link(), Some(nodes[0]));
+    assert_eq!(cursor.current_index(), Some(0));
+    assert_eq!(values(&list), [1]);
+    assert_eq!(values(&tail_2), [2]);
+
+    free_nodes(nodes);
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn test_apply_permutation()
+  {
+    let (mut list, nodes) = list_from(&[10, 20, 30]);
+    list.apply_permutation(&[2, 0, 1]);
+    assert_eq!(values(&list), [30, 10, 20]);
+    assert_eq!(list.front, Some(nodes[2]));
+    assert_eq!(list.back, Some(nodes[1]));
+
+    let mut empty = List::<usize>::new();
+    empty.apply_permutation(&[]);
+    assert!(empty.is_empty());
+
+    free_nodes(nodes);
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  #[should_panic]
+  fn test_apply_permutation_repeat()
+  {
+    let mut storage = [10, 20, 30].map(Node::new);
+    let (mut list, _) = list_on_stack(&mut storage);
+    list.apply_permutation(&[2, 0, 2]);
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn test_coalesce_next()
+  {
+    let mut list: List<usize> = [1, 2, 3, 10, 20]
+      .into_iter()
+      .map(|x| NonNull::from(Box::leak(Box::new(Node::new(x)))))
+      .collect();
+    let mut cursor = list.cursor_mut();
+    unsafe {
+      assert!(!cursor.coalesce_next(|a, b| *a += b));
+      cursor.move_next();
+      assert!(cursor.coalesce_next(|a, b| *a += b));
+      assert!(cursor.coalesce_next(|a, b| *a += b));
+      assert_eq!(cursor.current_value(), Some(&mut 6));
+      assert_eq!(cursor.current_index(), Some(0));
+      cursor.move_next();
+      assert!(cursor.coalesce_next(|a, b| *a += b));
+      assert!(!cursor.coalesce_next(|a, b| *a += b));
+    }
+    assert_eq!(values(&list), [6, 30]);
+
+    free_boxed(list);
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn test_sort_by_key()
+  {
+    let (mut list, nodes) = list_from(&[("b", 2), ("a", 1), ("b", 1), ("a", 3)]);
+    list.sort_by_key(|x| x.0);
+    assert_eq!(values(&list), [("a", 1), ("a", 3), ("b", 2), ("b", 1)]);
+    assert_eq!(
+      list.iter_nodes().collect::<Vec<_>>(),
+      [nodes[1], nodes[3], nodes[0], nodes[2]]
+    );
+
+    list.sort_by_key(|x| core::cmp::Reverse(x.1));
+    assert_eq!(values(&list), [("a", 3), ("b", 2), ("a", 1), ("b", 1)]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_insert_before_from_iter()
+  {
+    let (mut list, nodes) = list_from(&[10, 20]);
+    let mut added = Vec::new();
+    let mut cursor = list.cursor_mut();
+    cursor.step(2);
+    cursor.insert_before_from_iter((0..3).map(alloc_node).inspect(|&x| added.push(x)));
+    assert_eq!(cursor.current_index(), Some(4));
+    assert_eq!(cursor.current_value(), Some(&mut 20));
+
+    // On the ghost they go onto the back
+    let mut cursor = list.cursor_mut();
+    cursor.insert_before_from_iter((5..7).map(alloc_node).inspect(|&x| added.push(x)));
+    assert_eq!(values(&list), [10, 0, 1, 2, 20, 5, 6]);
+
+    free_nodes(nodes);
+    free_nodes(added);
+  }
+
+  #[test]
+  fn test_swap_remove()
+  {
+    let (mut list, nodes) = list_from(&[1, 2, 3, 4]);
+    assert_eq!(list.swap_remove(1), Some(nodes[1]));
+    assert_eq!(values(&list), [1, 4, 3]);
+    assert_eq!(list.iter_nodes().nth(1), Some(nodes[3]));
+    unsafe { assert_eq!(nodes[1].as_ref().links(), (None, None)) };
+
+    assert_eq!(list.swap_remove(2), Some(nodes[2]));
+    assert_eq!(values(&list), [1, 4]);
+    assert_eq!(list.swap_remove(2), None);
+    assert_eq!(list.swap_remove(0), Some(nodes[0]));
+    assert_eq!(values(&list), [4]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_find_next_node()
+  {
+    let (mut list, nodes) = list_from(&[2, 3, 5, 6, 7, 8]);
+    let mut cursor = list.cursor_mut();
+    assert_eq!(cursor.find_next_node(|x| x % 2 == 0), Some(nodes[0]));
+    cursor.step(2);
+    assert_eq!(cursor.find_next_node(|x| x % 2 == 0), Some(nodes[3]));
+    assert_eq!(cursor.current_index(), Some(1));
+    assert_eq!(cursor.find_next_node(|x| *x > 10), None);
+
+    // Feeding it into a node based removal
+    let found = cursor.find_next_node(|x| x % 2 == 0).unwrap();
+    list.bulk_remove_nodes(&[found]);
+    assert_eq!(values(&list), [2, 3, 5, 7, 8]);
+
+    free_nodes(nodes);
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn test_chunks()
+  {
+    let (list, nodes) = list_from(&[1, 2, 3, 4, 5]);
+    let chunks: Vec<Vec<&i32>> = list.chunks(2).collect();
+    assert_eq!(chunks, [vec![&1, &2], vec![&3, &4], vec![&5]]);
+    assert_eq!(list.chunks(5).count(), 1);
+    assert_eq!(list.chunks(9).next().unwrap().len(), 5);
+    assert_eq!(List::<usize>::new().chunks(3).count(), 0);
+    assert_eq!(values(&list), [1, 2, 3, 4, 5]);
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_remove_matching_forward()
+  {
+    let (mut list, nodes) = list_from(&[1, 3, 5, 6, 7, 8, 9]);
+    let mut cursor = list.cursor_mut();
+    cursor.step(3);
+    let removed = cursor.remove_matching_forward(|x| x % 2 == 1);
+    assert_eq!(cursor.current_link(), None);
+    cursor.move_prev();
+    assert_eq!(cursor.current_value(), Some(&mut 8));
+    assert_eq!(cursor.current_index(), Some(3));
+    assert_eq!(values(&list), [1, 3, 6, 8]);
+    assert_eq!(values(&removed), [5, 7, 9]);
+
+    // Nothing to sweep from the ghost
+    assert!(
+      list
+        .cursor_mut()
+        .remove_matching_forward(|_| true)
+        .is_empty()
+    );
+
+    free_nodes(nodes);
+  }
+
+  #[test]
+  fn test_push_resets_stale_links()
+  {
+    let (mut old, nodes) = list_from(&[1, 2, 3]);
+    // Orphan the nodes with their links to each other still set
+    old.clear_fast();
+
+    let mut list = List::new();
+    list.push_back(nodes[1]);
+    assert_eq!(values(&list), [2]);
+    list.push_front(nodes[2]);
+    list.push_back(nodes[0]);
+    assert_eq!(values(&list), [3, 2, 1]);
+    unsafe {
+      assert_eq!(nodes[2].as_ref().links(), (None, Some(nodes[1])));
+      assert_eq!(nodes[0].as_ref().links(), (Some(nodes[1]), None));
+    }
+
+    free_nodes(nodes);
+  }
+
+  // Aliasing checks meant for Miri, run the whole suite under it with
+  //   MIRIFLAGS=-Zmiri-strict-provenance cargo +nightly miri test
+  // They pass as plain tests too, but only Miri can tell whether the references they keep alive
+  // actually overlap. Tests that panic partway keep their nodes on the stack so the leak check holds.
+  mod aliasing
+  {
+    use super::{free_nodes, list_from, values};
+
+    // Miri is slow, a plain run can afford a longer list
+    const LEN: usize = if cfg!(miri) { 100 } else { 1000 };
+
+    // Every &mut IterMut hands out is kept alive and read back after later ones, which is only sound
+    // if it steps past each node before yielding its element
+    #[test]
+    fn test_iter_mut_disjoint()
+    {
+      let input: Vec<usize> = (0..LEN).collect();
+      let (mut list, nodes) = list_from(&input);
+      let mut held: Vec<&mut usize> = Vec::new();
+      for x in list.iter_mut()
+      {
+        if let Some(last) = held.last()
+        {
+          assert_eq!(**last / 2 + 1, *x);
+        }
+        *x *= 2;
+        held.push(x);
+      }
+      assert_eq!(held.len(), LEN);
+      for (i, x) in held.iter_mut().enumerate()
+      {
+        assert_eq!(**x, i * 2);
+        **x += 1;
+      }
+      assert_eq!(
+        values(&list),
+        (0..LEN).map(|x| x * 2 + 1).collect::<Vec<_>>()
+      );
+
+      free_nodes(nodes);
     }
   }
 }